@@ -3,13 +3,13 @@ extern crate anyhow;
 
 use std::{io::Cursor};
 use anyhow::Result;
-use libra::vm::errors::{BinaryLoaderResult, PartialVMError};
+use libra::vm::errors::PartialVMError;
 use libra::libra_types::account_address::AccountAddress;
-use libra::move_core_types::vm_status::StatusCode;
 use libra::vm::file_format_common::{TableType};
 use libra::vm::file_format_common::*;
 use libra::vm::file_format::SignatureToken;
 use libra::vm::deserializer::{check_binary, load_signature_token, load_constant_size};
+use libra::vm::file_format::{CompiledModule, CompiledScript};
 
 mod context;
 mod mutator;
@@ -20,33 +20,145 @@ use crate::mutator::Mutator;
 const DFIN_ADDR_LEN: usize = AccountAddress::LENGTH;
 const LIBRA_ADDR_LEN: usize = 16;
 
+/// Width of account addresses the binary is compiled for.
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum AddressWidth {
+    /// 16-byte addresses.
+    Libra,
+    /// 20-byte addresses, `AccountAddress::LENGTH`.
+    Dfinance,
+}
+
+impl AddressWidth {
+    pub fn len(self) -> usize {
+        match self {
+            AddressWidth::Libra => LIBRA_ADDR_LEN,
+            AddressWidth::Dfinance => DFIN_ADDR_LEN,
+        }
+    }
+}
+
+/// Widens Libra addresses to the Dfinance ones.
 pub fn adapt(bytes: &mut Vec<u8>) -> Result<()> {
+    convert(bytes, AddressWidth::Libra, AddressWidth::Dfinance)
+}
+
+/// Narrows Dfinance addresses to the Libra ones.
+/// Fails if any address doesn't fit, i.e. its leading bytes are non-zero.
+pub fn narrow(bytes: &mut Vec<u8>) -> Result<()> {
+    convert(bytes, AddressWidth::Dfinance, AddressWidth::Libra)
+}
+
+/// Detects the width of the binary and converts it to the `target` one.
+/// Returns the detected width.
+pub fn adapt_to(bytes: &mut Vec<u8>, target: AddressWidth) -> Result<AddressWidth> {
+    let width = detect(bytes)?;
+    convert(bytes, width, target)?;
+    Ok(width)
+}
+
+/// Converts addresses in address identifiers and the constant pool from one width to another.
+pub fn convert(bytes: &mut Vec<u8>, from: AddressWidth, to: AddressWidth) -> Result<()> {
+    if from == to {
+        return Ok(());
+    }
     let mut cur = Cursor::new(bytes.as_slice());
     let mut mutator = Mutator::new();
 
     check_binary(&mut cur).map_err(|err| anyhow!("{:?}", err))?;
-    make_diff(&mut cur, &mut mutator)?;
+    make_diff(&mut cur, &mut mutator, from, to)?;
     mutator.mutate(bytes);
     Ok(())
 }
 
-fn make_diff(cur: &mut Cursor<&[u8]>, mutator: &mut Mutator) -> Result<()> {
+/// Detects which address width the binary is compiled for.
+///
+/// Widths are told apart by the size of the address identifiers table and sizes
+/// of address constants. If both widths fit, the binary is checked by the deserializer.
+/// Binaries without addresses are reported as `Dfinance` since they need no conversion.
+pub fn detect(bytes: &[u8]) -> Result<AddressWidth> {
+    let mut cur = Cursor::new(bytes);
+    check_binary(&mut cur).map_err(|err| anyhow!("{:?}", err))?;
+    let (table_len, const_sizes) = address_sizes(&mut cur)?;
+
+    let fits = |width: AddressWidth| {
+        table_len % width.len() as u32 == 0
+            && const_sizes.iter().all(|size| *size == width.len() as u32)
+    };
+    match (fits(AddressWidth::Libra), fits(AddressWidth::Dfinance)) {
+        (true, false) => Ok(AddressWidth::Libra),
+        (false, true) => Ok(AddressWidth::Dfinance),
+        (false, false) => Err(anyhow!(
+            "Address identifiers ({} bytes) don't match any address width",
+            table_len
+        )),
+        (true, true) if table_len == 0 => Ok(AddressWidth::Dfinance),
+        (true, true) => {
+            if CompiledModule::deserialize(bytes).is_ok()
+                || CompiledScript::deserialize(bytes).is_ok()
+            {
+                Ok(AddressWidth::Dfinance)
+            } else {
+                Ok(AddressWidth::Libra)
+            }
+        }
+    }
+}
+
+/// Length of the address identifiers table and sizes of address constants.
+fn address_sizes(cur: &mut Cursor<&[u8]>) -> Result<(u32, Vec<u32>)> {
+    let table_len = read_uleb128_as_u64(cur)?;
+    let header_len = cur.position() as u32;
+    let header_size = calc_header_size(cur, table_len)?;
+
+    let mut addr_len = 0;
+    let mut const_sizes = Vec::new();
+    for _ in 0..table_len {
+        let kind = read_u8(cur)?;
+        let offset = read_uleb128_as_u64(cur)? as u32;
+        let t_len = read_uleb128_as_u64(cur)? as u32;
+
+        if kind == TableType::ADDRESS_IDENTIFIERS as u8 {
+            addr_len += t_len;
+        } else if kind == TableType::CONSTANT_POOL as u8 {
+            let ctx = TableContext::new(cur, offset + header_size + header_len, t_len);
+            let end_offset = ctx.cursor.position() + ctx.len as u64;
+            while ctx.cursor.position() < end_offset {
+                let type_ = load_signature_token(ctx.cursor).map_err(|err| anyhow!("{:?}", err))?;
+                let size =
+                    load_constant_size(ctx.cursor).map_err(|err| anyhow!("{:?}", err))? as u32;
+                if SignatureToken::Address == type_ {
+                    const_sizes.push(size);
+                }
+                ctx.cursor.set_position(ctx.cursor.position() + size as u64);
+            }
+        }
+    }
+    Ok((addr_len, const_sizes))
+}
+
+fn make_diff(
+    cur: &mut Cursor<&[u8]>,
+    mutator: &mut Mutator,
+    from: AddressWidth,
+    to: AddressWidth,
+) -> Result<()> {
     let table_len = read_uleb128_as_u64(cur)?;
 
     let header_len = cur.position() as u32;
     let header_size = calc_header_size(cur, table_len)?;
 
-    let mut additional_offset: u32 = 0;
+    let mut additional_offset: i64 = 0;
     for _ in 0..table_len {
         let kind = read_u8(cur)?;
 
-        let offset = if additional_offset > 0 {
+        let offset = if additional_offset != 0 {
             let start_pos = cur.position();
             let offset = read_uleb128_as_u64(cur)? as u32;
             make_uleb128_diff(
                 start_pos,
                 cur.position(),
-                offset + additional_offset,
+                (offset as i64 + additional_offset) as u32,
                 mutator,
             )?;
             offset
@@ -62,19 +174,27 @@ fn make_diff(cur: &mut Cursor<&[u8]>, mutator: &mut Mutator) -> Result<()> {
             handle_address_identifiers(
                 TableContext::new(cur, offset + header_size + header_len, t_len),
                 mutator,
-            )
+                from,
+                to,
+            )?
         } else if kind == TableType::CONSTANT_POOL as u8 {
             handle_const_pool(
                 TableContext::new(cur, offset + header_size + header_len, t_len),
                 mutator,
-            )
-            .map_err(|err| anyhow!("{:?}", err))?
+                from,
+                to,
+            )?
         } else {
             0
         };
 
-        if offset_diff > 0 {
-            make_uleb128_diff(t_len_start_pos, t_len_end_pos, t_len + offset_diff, mutator)?;
+        if offset_diff != 0 {
+            make_uleb128_diff(
+                t_len_start_pos,
+                t_len_end_pos,
+                (t_len as i64 + offset_diff) as u32,
+                mutator,
+            )?;
         }
 
         additional_offset += offset_diff;
@@ -109,46 +229,82 @@ fn make_uleb128_diff(
     Ok(())
 }
 
-fn handle_address_identifiers(ctx: TableContext, mutator: &mut Mutator) -> u32 {
-    if ctx.len % (LIBRA_ADDR_LEN as u32) == 0 {
-        for idx in (0..ctx.len).step_by(LIBRA_ADDR_LEN) {
+/// Pads the address at `index` with leading zeros or cuts them off.
+fn make_address_diff(
+    ctx: &TableContext,
+    index: usize,
+    mutator: &mut Mutator,
+    from: AddressWidth,
+    to: AddressWidth,
+) -> Result<()> {
+    if to.len() > from.len() {
+        mutator.make_diff(index, index, vec![0x0; to.len() - from.len()]);
+    } else {
+        let cut = from.len() - to.len();
+        let address = ctx
+            .cursor
+            .get_ref()
+            .get(index..index + from.len())
+            .ok_or_else(|| anyhow!("Address at {} is out of the binary", index))?;
+        if address[..cut].iter().any(|b| *b != 0) {
+            bail!(
+                "Address 0x{} doesn't fit into {} bytes",
+                hex::encode(address),
+                to.len()
+            );
+        }
+        mutator.make_diff(index, index + cut, vec![]);
+    }
+    Ok(())
+}
+
+fn handle_address_identifiers(
+    ctx: TableContext,
+    mutator: &mut Mutator,
+    from: AddressWidth,
+    to: AddressWidth,
+) -> Result<i64> {
+    let from_len = from.len() as u32;
+    if ctx.len % from_len == 0 {
+        for idx in (0..ctx.len).step_by(from.len()) {
             let index = ctx.position() + idx as usize;
-            mutator.make_diff(index, index, vec![0x0, 0x0, 0x0, 0x0]);
+            make_address_diff(&ctx, index, mutator, from, to)?;
         }
-        ctx.len / (LIBRA_ADDR_LEN as u32) * (DFIN_ADDR_LEN - LIBRA_ADDR_LEN) as u32
+        Ok((ctx.len / from_len) as i64 * (to.len() as i64 - from.len() as i64))
     } else {
-        0
+        Ok(0)
     }
 }
 
-fn handle_const_pool(ctx: TableContext, mutator: &mut Mutator) -> BinaryLoaderResult<u32> {
+fn handle_const_pool(
+    ctx: TableContext,
+    mutator: &mut Mutator,
+    from: AddressWidth,
+    to: AddressWidth,
+) -> Result<i64> {
+    let loader_err = |err: PartialVMError| anyhow!("{:?}", err);
     let end_offset = ctx.cursor.position() + ctx.len as u64;
     let mut additional_offset = 0;
     while ctx.cursor.position() < end_offset {
-        let type_ = load_signature_token(ctx.cursor)?;
+        let type_ = load_signature_token(ctx.cursor).map_err(loader_err)?;
 
         let size_start_offset = ctx.cursor.position();
-        let size = load_constant_size(ctx.cursor)? as u32;
+        let size = load_constant_size(ctx.cursor).map_err(loader_err)? as u32;
         let size_end_offset = ctx.cursor.position();
 
-        if SignatureToken::Address == type_ {
-            let diff_size = (DFIN_ADDR_LEN - LIBRA_ADDR_LEN) as u32;
+        if SignatureToken::Address == type_ && size as usize == from.len() {
+            let diff_size = to.len() as i64 - from.len() as i64;
             make_uleb128_diff(
                 size_start_offset,
                 size_end_offset,
-                size + diff_size,
+                (size as i64 + diff_size) as u32,
                 mutator,
-            )
-            .map_err(|err| {
-                PartialVMError::new(StatusCode::MALFORMED).with_message(format!("{:?}", err))
-            })?;
+            )?;
             additional_offset += diff_size;
             let index = ctx.cursor.position() as usize;
-            mutator.make_diff(index, index, vec![0x0, 0x0, 0x0, 0x0]);
-            ctx.cursor.set_position(ctx.cursor.position() + size as u64);
-        } else {
-            ctx.cursor.set_position(ctx.cursor.position() + size as u64);
+            make_address_diff(&ctx, index, mutator, from, to)?;
         }
+        ctx.cursor.set_position(ctx.cursor.position() + size as u64);
     }
 
     Ok(additional_offset)
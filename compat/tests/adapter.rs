@@ -2,7 +2,7 @@
 extern crate include_dir;
 use libra::vm::file_format::{CompiledModule, CompiledScript};
 use include_dir::Dir;
-use move_compat::adapt;
+use move_compat::{adapt, adapt_to, detect, narrow, AddressWidth};
 
 static MODULES_TESTS_DIR: Dir = include_dir!("tests/libra_std/modules");
 static SCRIPTS_TESTS_DIR: Dir = include_dir!("tests/libra_std/scripts");
@@ -57,3 +57,58 @@ enum UnitType {
     Module,
     Script,
 }
+
+#[test]
+pub fn test_roundtrip() {
+    for file in MODULES_TESTS_DIR
+        .files()
+        .iter()
+        .chain(SCRIPTS_TESTS_DIR.files())
+    {
+        let origin = file.contents().to_vec();
+        assert_eq!(detect(&origin).unwrap(), AddressWidth::Libra);
+
+        let mut content = origin.clone();
+        adapt(&mut content).expect("Valid bytecode.");
+        assert_eq!(detect(&content).unwrap(), AddressWidth::Dfinance);
+
+        narrow(&mut content).expect("Addresses fit into Libra width.");
+        assert_eq!(content, origin, "{:?}", file.path());
+    }
+}
+
+#[test]
+pub fn test_adapt_to() {
+    let origin = include_bytes!("libra_std/modules/3_U.mv").to_vec();
+    let mut content = origin.clone();
+    assert_eq!(
+        adapt_to(&mut content, AddressWidth::Dfinance).unwrap(),
+        AddressWidth::Libra
+    );
+    CompiledModule::deserialize(&content).unwrap();
+
+    let adapted = content.clone();
+    assert_eq!(
+        adapt_to(&mut content, AddressWidth::Dfinance).unwrap(),
+        AddressWidth::Dfinance
+    );
+    assert_eq!(content, adapted);
+}
+
+#[test]
+pub fn test_narrow_wide_address() {
+    let mut content = include_bytes!("libra_std/modules/3_U.mv").to_vec();
+    adapt(&mut content).expect("Valid bytecode.");
+
+    // the 0x1 address of the module padded to 20 bytes:
+    let mut address = vec![0x0; AddressWidth::Dfinance.len()];
+    *address.last_mut().unwrap() = 0x1;
+    let index = content
+        .windows(address.len())
+        .position(|w| w == address.as_slice())
+        .expect("Address of the module.");
+    content[index] = 0xff;
+
+    let err = narrow(&mut content).unwrap_err();
+    assert!(err.to_string().contains("doesn't fit"), "{}", err);
+}
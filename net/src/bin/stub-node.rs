@@ -0,0 +1,38 @@
+//! Serves modules from a local directory like a node does.
+//!
+//! Usage: `stub-node <modules directory> [listen address]`,
+//! then pass `--data-source http://<listen address>/` to the unscrambler.
+
+use move_unscrambler_network::stub::{ModuleStore, StubNode};
+
+const DEFAULT_ADDRESS: &str = "127.0.0.1:9090";
+
+fn main() {
+    // skipped files and failed responses are reported as warnings:
+    env_logger::Builder::from_env(env_logger::Env::default().default_filter_or("info")).init();
+
+    let mut args = std::env::args().skip(1);
+    let dir = match args.next() {
+        Some(dir) => dir,
+        None => {
+            eprintln!("Usage: stub-node <modules directory> [listen address]");
+            std::process::exit(2);
+        }
+    };
+    let addr = args.next().unwrap_or_else(|| DEFAULT_ADDRESS.to_owned());
+
+    let node = ModuleStore::load_dir(&dir).and_then(|store| {
+        println!("{} modules loaded from {}", store.len(), dir);
+        StubNode::start(&addr, store)
+    });
+    match node {
+        Ok(node) => {
+            println!("Listening on {}", node.url());
+            node.join();
+        }
+        Err(err) => {
+            eprintln!("{}", err);
+            std::process::exit(1);
+        }
+    }
+}
@@ -1,3 +1,4 @@
+#[macro_use]
 extern crate log;
 #[macro_use]
 extern crate anyhow;
@@ -9,6 +10,8 @@ use libra::move_core_types::language_storage::ModuleId;
 use libra::move_core_types::identifier::Identifier;
 use libra::libra_types::access_path::AccessPath;
 
+pub mod stub;
+
 pub fn get<S>(
     addr: &AccountAddress,
     name: impl Into<Box<str>>,
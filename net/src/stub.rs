@@ -0,0 +1,151 @@
+//! Stand-in node serving modules from a local directory by the same REST API as `get` uses.
+
+use std::collections::HashMap;
+use std::path::Path;
+use std::sync::Arc;
+use std::thread::JoinHandle;
+use anyhow::Error;
+use tiny_http::{Header, Method, Request, Response as HttpResponse, Server};
+use libra::libra_types::access_path::AccessPath;
+use libra::vm::access::ModuleAccess;
+use libra::vm::CompiledModule;
+use super::{LoaderErrorResponse, LoaderResponse, Response};
+
+const DATA_PATH_PREFIX: &str = "/vm/data/";
+
+/// Modules by `{address}/{path}` of their access paths in hex.
+#[derive(Debug, Clone, Default)]
+pub struct ModuleStore {
+    modules: HashMap<String, Vec<u8>>,
+}
+
+impl ModuleStore {
+    /// Loads all `.mv` files in the `dir`, files which aren't modules are skipped.
+    pub fn load_dir<P: AsRef<Path>>(dir: P) -> Result<Self, Error> {
+        let mut store = Self::default();
+        let mut files: Vec<_> = std::fs::read_dir(dir)?
+            .filter_map(|e| e.ok())
+            .map(|e| e.path())
+            .filter(|p| p.is_file() && p.extension().map(|ext| ext == "mv").unwrap_or_default())
+            .collect();
+        files.sort();
+
+        for path in files {
+            let bytes = std::fs::read(&path)?;
+            if let Err(err) = store.insert(bytes) {
+                warn!("skip {}: {}", path.display(), err);
+            }
+        }
+        Ok(store)
+    }
+
+    /// Adds the module and returns its access path as `{address}/{path}`.
+    pub fn insert(&mut self, bytes: Vec<u8>) -> Result<String, Error> {
+        let module = CompiledModule::deserialize(&bytes)
+            .map_err(|err| anyhow!("Invalid module: {:?}", err))?;
+        let path = AccessPath::code_access_path(&module.self_id());
+        let key = format!("{}/{}", hex::encode(&path.address), hex::encode(path.path));
+        debug!("serving {:?} as {}", module.self_id(), key);
+        self.modules.insert(key.clone(), bytes);
+        Ok(key)
+    }
+
+    pub fn len(&self) -> usize {
+        self.modules.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.modules.is_empty()
+    }
+
+    fn get(&self, url: &str) -> Option<&Vec<u8>> {
+        let key = url.strip_prefix(DATA_PATH_PREFIX)?;
+        self.modules.get(key.trim_end_matches('/'))
+    }
+}
+
+/// Running stand-in node, stopped on drop.
+pub struct StubNode {
+    server: Arc<Server>,
+    url: String,
+    worker: Option<JoinHandle<()>>,
+}
+
+impl StubNode {
+    /// Starts serving the `store` on the `addr`, e.g. `127.0.0.1:0` for any free port.
+    pub fn start(addr: &str, store: ModuleStore) -> Result<Self, Error> {
+        let server = Arc::new(Server::http(addr).map_err(|err| anyhow!("{}", err))?);
+        let url = format!("http://{}/", server.server_addr());
+
+        let worker = {
+            let server = server.clone();
+            std::thread::spawn(move || {
+                for request in server.incoming_requests() {
+                    if let Err(err) = respond(&store, request) {
+                        warn!("unable to respond: {}", err);
+                    }
+                }
+            })
+        };
+
+        info!("stub node is listening on {}", url);
+        Ok(Self {
+            server,
+            url,
+            worker: Some(worker),
+        })
+    }
+
+    /// Base URL of the node to pass as data-source.
+    pub fn url(&self) -> &str {
+        &self.url
+    }
+
+    /// Serves requests until the process is stopped.
+    pub fn join(mut self) {
+        if let Some(worker) = self.worker.take() {
+            worker.join().ok();
+        }
+    }
+}
+
+impl Drop for StubNode {
+    fn drop(&mut self) {
+        self.server.unblock();
+        if let Some(worker) = self.worker.take() {
+            worker.join().ok();
+        }
+    }
+}
+
+fn respond(store: &ModuleStore, request: Request) -> Result<(), Error> {
+    trace!("{} {}", request.method(), request.url());
+    let found = match request.method() {
+        Method::Get => store.get(request.url()),
+        _ => None,
+    };
+    let (status, body) = match found {
+        Some(bytes) => (
+            200,
+            serde_json::to_string(&LoaderResponse {
+                result: Response {
+                    value: hex::encode(bytes),
+                },
+            })?,
+        ),
+        None => (
+            404,
+            serde_json::to_string(&LoaderErrorResponse {
+                error: format!("Not found: {}", request.url()),
+            })?,
+        ),
+    };
+
+    let header = Header::from_bytes(&b"Content-Type"[..], &b"application/json"[..])
+        .map_err(|_| anyhow!("Invalid header"))?;
+    let response = HttpResponse::from_string(body)
+        .with_status_code(status)
+        .with_header(header);
+    request.respond(response)?;
+    Ok(())
+}
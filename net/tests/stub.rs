@@ -0,0 +1,23 @@
+use libra::libra_types::account_address::AccountAddress;
+use libra::vm::access::ModuleAccess;
+use libra::vm::CompiledModule;
+use move_unscrambler_network::{get, NetCfg};
+use move_unscrambler_network::stub::{ModuleStore, StubNode};
+
+#[test]
+pub fn serve_module() {
+    let mut bytes = include_bytes!("../../compat/tests/libra_std/modules/3_U.mv").to_vec();
+    compat::adapt(&mut bytes).expect("Valid bytecode.");
+    let id = CompiledModule::deserialize(&bytes).unwrap().self_id();
+
+    let mut store = ModuleStore::default();
+    store.insert(bytes.clone()).unwrap();
+    let node = StubNode::start("127.0.0.1:0", store).unwrap();
+    let cfg = NetCfg::new(node.url());
+
+    let served = get(id.address(), id.name().as_str(), &cfg).unwrap();
+    assert_eq!(served, bytes);
+
+    assert!(get(id.address(), "Missing", &cfg).is_err());
+    assert!(get(&AccountAddress::random(), id.name().as_str(), &cfg).is_err());
+}
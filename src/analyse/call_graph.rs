@@ -0,0 +1,120 @@
+use std::collections::{HashMap, HashSet, VecDeque};
+use crate::extract::prelude::*;
+use crate::types::FnAddr;
+
+/// Directed graph of calls between known functions.
+#[derive(Debug, Default, Clone)]
+pub struct CallGraph {
+    callees: HashMap<FnAddr, Vec<FnAddr>>,
+    callers: HashMap<FnAddr, Vec<FnAddr>>,
+}
+
+impl CallGraph {
+    pub fn new(functions: &FnMap) -> Self {
+        let mut graph = Self::default();
+        for (caller, info) in functions.iter() {
+            graph
+                .callees
+                .insert(caller.to_owned(), info.calls.to_owned());
+            for callee in info.calls.iter() {
+                graph
+                    .callers
+                    .entry(callee.to_owned())
+                    .or_default()
+                    .push(caller.to_owned());
+            }
+        }
+        // callers are collected in random order of the map, so make them stable:
+        for callers in graph.callers.values_mut() {
+            callers.sort_by_key(|f| format!("{:#x}", f));
+        }
+        graph
+    }
+
+    /// Direct callees of the function.
+    pub fn callees(&self, f: &FnAddr) -> &[FnAddr] {
+        self.callees.get(f).map(|v| &v[..]).unwrap_or(&[])
+    }
+
+    /// Known functions that directly call the function.
+    pub fn callers(&self, f: &FnAddr) -> &[FnAddr] {
+        self.callers.get(f).map(|v| &v[..]).unwrap_or(&[])
+    }
+
+    /// All functions reachable from the function, in BFS order.
+    /// Callees of unresolved functions are unknown, so the result can be incomplete.
+    pub fn callees_transitive(&self, f: &FnAddr) -> Vec<FnAddr> {
+        let mut visited: HashSet<&FnAddr> = HashSet::new();
+        let mut result = Vec::new();
+        let mut queue: VecDeque<&FnAddr> = self.callees(f).iter().collect();
+
+        while let Some(callee) = queue.pop_front() {
+            if !visited.insert(callee) {
+                continue;
+            }
+            result.push(callee.to_owned());
+            queue.extend(self.callees(callee).iter());
+        }
+        result
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use libra::vm::file_format::Bytecode::*;
+    use crate::fixtures;
+    use super::*;
+
+    /// `sequence_number` and `balance` call each other, `pay_from` calls `sequence_number`.
+    pub(crate) fn mutual_recursion() -> FnMap {
+        let m = fixtures::with_bodies(
+            "31_LibraAccount",
+            &["sequence_number", "balance", "pay_from"],
+            |name, f, s| match name {
+                "sequence_number" => {
+                    vec![Call(f("balance")), MutBorrowGlobal(s("LibraAccount")), Ret]
+                }
+                "balance" => vec![
+                    Call(f("sequence_number")),
+                    MoveFrom(s("AccountOperationsCapability")),
+                    Ret,
+                ],
+                _ => vec![Call(f("sequence_number")), Ret],
+            },
+        );
+        extract_functions(&m, false)
+    }
+
+    pub(crate) fn account_fn(name: &str) -> FnAddr {
+        format!("0x1::LibraAccount::{}", name).parse().unwrap()
+    }
+
+    #[test]
+    fn direct_calls() {
+        let graph = CallGraph::new(&mutual_recursion());
+        assert_eq!(
+            graph.callees(&account_fn("sequence_number")),
+            &[account_fn("balance")]
+        );
+        // other functions of the module can call it as well:
+        let callers = graph.callers(&account_fn("sequence_number"));
+        let balance = callers.iter().position(|f| f == &account_fn("balance"));
+        let pay_from = callers.iter().position(|f| f == &account_fn("pay_from"));
+        assert!(balance.unwrap() < pay_from.unwrap(), "{:?}", callers);
+        assert!(graph.callees(&account_fn("missing")).is_empty());
+    }
+
+    #[test]
+    fn transitive_calls_with_cycle() {
+        let graph = CallGraph::new(&mutual_recursion());
+        assert_eq!(
+            graph.callees_transitive(&account_fn("pay_from")),
+            vec![account_fn("sequence_number"), account_fn("balance")]
+        );
+        // the recursive function reaches itself once:
+        assert_eq!(
+            graph.callees_transitive(&account_fn("sequence_number")),
+            vec![account_fn("balance"), account_fn("sequence_number")]
+        );
+    }
+}
@@ -0,0 +1,118 @@
+use std::collections::HashMap;
+use serde::Serialize;
+use libra::bytecode_verifier::control_flow_graph::{ControlFlowGraph, VMControlFlowGraph};
+use libra::vm::file_format::{Bytecode, CodeOffset, CodeUnit};
+use crate::extract::prelude::*;
+use crate::types::{BlockAddr, FnAddr};
+
+pub type CfgMap = HashMap<FnAddr, FnCfg>;
+
+/// Control-flow graph of the function body.
+#[derive(Debug, Clone, Serialize)]
+pub struct FnCfg {
+    pub address: FnAddr,
+    /// Basic blocks ordered by offset, the entry block goes first.
+    pub blocks: Vec<BasicBlock>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct BasicBlock {
+    pub address: BlockAddr,
+    pub start: CodeOffset,
+    pub end: CodeOffset,
+    /// Disassembled instructions of the block.
+    pub code: Vec<String>,
+    pub edges: Vec<Edge>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Edge {
+    pub target: BlockAddr,
+    pub kind: EdgeKind,
+}
+
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Serialize)]
+pub enum EdgeKind {
+    /// Unconditional `Branch`.
+    Jump,
+    /// Taken if the condition is true.
+    True,
+    /// Taken if the condition is false.
+    False,
+    /// Next block without a branch instruction.
+    Fallthrough,
+}
+
+impl FnCfg {
+    /// Builds the graph, `LdConst` instructions are annotated with values from `constants`.
+    pub fn new(address: &FnAddr, code: &CodeUnit, constants: &[ConstInfo]) -> Self {
+        let cfg = VMControlFlowGraph::new(&code.code);
+        let mut ids = cfg.blocks();
+        ids.sort();
+
+        let blocks = ids
+            .into_iter()
+            .map(|id| {
+                let start = cfg.block_start(id);
+                let end = cfg.block_end(id);
+                let last = &code.code[end as usize];
+                let edges = cfg
+                    .successors(id)
+                    .iter()
+                    .map(|&target| Edge {
+                        target: BlockAddr::new(address.to_owned(), target),
+                        kind: edge_kind(last, target),
+                    })
+                    .collect();
+                BasicBlock {
+                    address: BlockAddr::new(address.to_owned(), id),
+                    start,
+                    end,
+                    code: code.code[start as usize..=end as usize]
+                        .iter()
+                        .map(|op| match op {
+                            Bytecode::LdConst(idx) => match constants.get(idx.0 as usize) {
+                                Some(c) => format!("{:?} // {}", op, c.value),
+                                None => format!("{:?}", op),
+                            },
+                            _ => format!("{:?}", op),
+                        })
+                        .collect(),
+                    edges,
+                }
+            })
+            .collect();
+
+        Self {
+            address: address.to_owned(),
+            blocks,
+        }
+    }
+}
+
+fn edge_kind(last: &Bytecode, target: CodeOffset) -> EdgeKind {
+    match last {
+        Bytecode::Branch(_) => EdgeKind::Jump,
+        Bytecode::BrTrue(offset) if *offset == target => EdgeKind::True,
+        Bytecode::BrTrue(_) => EdgeKind::False,
+        Bytecode::BrFalse(offset) if *offset == target => EdgeKind::False,
+        Bytecode::BrFalse(_) => EdgeKind::True,
+        _ => EdgeKind::Fallthrough,
+    }
+}
+
+/// Builds control-flow graphs of all functions with a body.
+pub fn build_cfg_map(functions: &FnMap, constants: &ConstMap) -> CfgMap {
+    functions
+        .iter()
+        .filter_map(|(addr, info)| {
+            let pool = constants
+                .get(addr.addr())
+                .map(Vec::as_slice)
+                .unwrap_or_default();
+            info.code
+                .as_ref()
+                .map(|code| (addr.to_owned(), FnCfg::new(addr, code, pool)))
+        })
+        .collect()
+}
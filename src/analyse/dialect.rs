@@ -0,0 +1,164 @@
+use libra::libra_types::account_address::AccountAddress;
+use compat::AddressWidth;
+use crate::cli::Dialect;
+use crate::disasm::CompiledMove;
+use crate::extract::prelude::*;
+use crate::types::ModAddr;
+
+/// Modules of the Libra standard library which aren't in the Dfinance one.
+const LIBRA_STD: &[&str] = &[
+    "LibraAccount",
+    "Libra",
+    "LBR",
+    "Coin1",
+    "Coin2",
+    "LibraConfig",
+    "LibraTimestamp",
+    "LibraSystem",
+    "Roles",
+    "DesignatedDealer",
+    "AccountLimits",
+];
+
+/// Modules of the Dfinance standard library which aren't in the Libra one.
+const DFINANCE_STD: &[&str] = &["Dfinance", "Coins", "DFI", "Account", "Oracle", "Time"];
+
+/// Dialect guessed from the bytecode.
+#[derive(Debug, Clone)]
+pub struct InferredDialect {
+    pub dialect: Dialect,
+    /// What the guess is based on.
+    pub reason: String,
+    /// Signal which points to the other dialect, the width of addresses wins.
+    pub conflict: Option<String>,
+}
+
+/// Infers the dialect from the raw (not adapted) bytecode `raw` and the deserialized `bc`.
+///
+/// Both the width of addresses and address constants
+/// and imports of well-known standard library modules at `0x1` are checked.
+/// If they disagree, the width is trusted and the conflict is reported.
+pub fn infer_dialect(raw: &[u8], bc: &CompiledMove) -> Option<InferredDialect> {
+    let imports = extract_mod_handles(bc);
+    let is_module = matches!(bc, CompiledMove::Module(_));
+
+    // scripts without imports have no addresses to measure:
+    let by_width = if is_module || !imports.is_empty() {
+        match compat::detect(raw) {
+            Ok(width) => {
+                let dialect = match width {
+                    AddressWidth::Libra => Dialect::Libra,
+                    AddressWidth::Dfinance => Dialect::Dfinance,
+                };
+                Some((dialect, format!("{}-byte addresses", width.len())))
+            }
+            Err(err) => {
+                debug!("unable to detect address width: {}", err);
+                None
+            }
+        }
+    } else {
+        None
+    };
+
+    let std_addr = {
+        let mut bytes = [0; AccountAddress::LENGTH];
+        bytes[AccountAddress::LENGTH - 1] = 1;
+        AccountAddress::new(bytes)
+    };
+    let std_import = |names: &[&str]| {
+        imports
+            .iter()
+            .find(|m| m.addr() == &std_addr && names.contains(&m.name()))
+            .cloned()
+    };
+    let by_imports = match (std_import(LIBRA_STD), std_import(DFINANCE_STD)) {
+        (Some(m), None) => Some((Dialect::Libra, format!("imports Libra module {}", m.name()))),
+        (None, Some(m)) => Some((
+            Dialect::Dfinance,
+            format!("imports Dfinance module {}", m.name()),
+        )),
+        _ => None,
+    };
+
+    let (dialect, reason, conflict) = match (by_width, by_imports) {
+        (Some((width, w_reason)), Some((imports, i_reason))) if width == imports => {
+            (width, format!("{}, {}", w_reason, i_reason), None)
+        }
+        (Some((width, w_reason)), Some((_, i_reason))) => (width, w_reason, Some(i_reason)),
+        (Some((dialect, reason)), None) | (None, Some((dialect, reason))) => {
+            (dialect, reason, None)
+        }
+        (None, None) => return None,
+    };
+    Some(InferredDialect {
+        dialect,
+        reason,
+        conflict,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::disasm::CompiledMove;
+    use crate::fixtures;
+    use super::*;
+
+    fn infer(raw: &[u8], bc: CompiledMove) -> InferredDialect {
+        infer_dialect(raw, &bc).unwrap()
+    }
+
+    #[test]
+    fn libra_module() {
+        let inferred = infer(
+            &fixtures::raw("modules/31_LibraAccount"),
+            CompiledMove::Module(fixtures::module("31_LibraAccount")),
+        );
+        assert_eq!(inferred.dialect, Dialect::Libra);
+        assert!(
+            inferred.reason.contains("16-byte addresses"),
+            "{}",
+            inferred.reason
+        );
+        assert!(
+            inferred.reason.contains("imports Libra module"),
+            "{}",
+            inferred.reason
+        );
+        assert!(inferred.conflict.is_none());
+    }
+
+    #[test]
+    fn libra_script() {
+        let inferred = infer(
+            &fixtures::raw("scripts/peer_to_peer_with_metadata"),
+            CompiledMove::Script(fixtures::script("peer_to_peer_with_metadata")),
+        );
+        assert_eq!(inferred.dialect, Dialect::Libra);
+        assert!(inferred.conflict.is_none());
+    }
+
+    #[test]
+    fn width_only() {
+        let inferred = infer(
+            &fixtures::adapted("modules/3_U"),
+            CompiledMove::Module(fixtures::module("3_U")),
+        );
+        assert_eq!(inferred.dialect, Dialect::Dfinance);
+        assert_eq!(inferred.reason, "20-byte addresses");
+        assert!(inferred.conflict.is_none());
+    }
+
+    #[test]
+    fn conflict() {
+        // 20-byte binary importing 0x1::LibraAccount:
+        let inferred = infer(
+            &fixtures::adapted("modules/31_LibraAccount"),
+            CompiledMove::Module(fixtures::module("31_LibraAccount")),
+        );
+        assert_eq!(inferred.dialect, Dialect::Dfinance);
+        assert_eq!(inferred.reason, "20-byte addresses");
+        let conflict = inferred.conflict.unwrap();
+        assert!(conflict.contains("imports Libra module"), "{}", conflict);
+    }
+}
@@ -0,0 +1,74 @@
+use crate::extract::prelude::*;
+use crate::types::FnAddr;
+use super::call_graph::CallGraph;
+
+/// Storage effects of the function merged with effects of all functions reachable from it.
+/// Unresolved callees are skipped, so the result can be incomplete.
+pub fn effects_transitive(functions: &FnMap, graph: &CallGraph, f: &FnAddr) -> StorageEffects {
+    let mut effects = functions
+        .get(f)
+        .map(|info| info.effects.to_owned())
+        .unwrap_or_default();
+    for callee in graph.callees_transitive(f) {
+        if let Some(info) = functions.get(&callee) {
+            effects.merge(&info.effects);
+        }
+    }
+    effects
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::types::StructAddr;
+    use super::super::call_graph::tests::{account_fn, mutual_recursion};
+    use super::*;
+
+    fn account_struct(name: &str) -> StructAddr {
+        format!("0x1::LibraAccount::{}", name).parse().unwrap()
+    }
+
+    #[test]
+    fn callee_effects_propagate() {
+        let functions = mutual_recursion();
+        let graph = CallGraph::new(&functions);
+
+        // own effects of the caller are empty:
+        assert!(functions[&account_fn("pay_from")].effects.is_empty());
+        let effects = effects_transitive(&functions, &graph, &account_fn("pay_from"));
+        assert_eq!(effects.mutates, vec![account_struct("LibraAccount")]);
+        assert_eq!(
+            effects.removes,
+            vec![account_struct("AccountOperationsCapability")]
+        );
+        assert!(effects.reads.is_empty());
+        assert!(effects.publishes.is_empty());
+    }
+
+    #[test]
+    fn cycle_merged_once() {
+        let functions = mutual_recursion();
+        let graph = CallGraph::new(&functions);
+        for f in &["sequence_number", "balance"] {
+            let effects = effects_transitive(&functions, &graph, &account_fn(f));
+            assert_eq!(
+                effects.mutates,
+                vec![account_struct("LibraAccount")],
+                "{}",
+                f
+            );
+            assert_eq!(
+                effects.removes,
+                vec![account_struct("AccountOperationsCapability")],
+                "{}",
+                f
+            );
+        }
+    }
+
+    #[test]
+    fn unknown_function() {
+        let functions = mutual_recursion();
+        let graph = CallGraph::new(&functions);
+        assert!(effects_transitive(&functions, &graph, &account_fn("missing")).is_empty());
+    }
+}
@@ -0,0 +1,266 @@
+use std::collections::HashSet;
+use anyhow::{format_err, Result};
+use crate::data::Db;
+use crate::extract::prelude::*;
+use crate::types::{FnAddr, ModAddr, StructAddr, Ty};
+use super::call_graph::CallGraph;
+
+/// Item the report is restricted to.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub enum Focus {
+    Function(FnAddr),
+    Struct(StructAddr),
+}
+
+impl Focus {
+    /// Looks up the `target` among known functions first, then among known structs.
+    /// Functions and structs share the `ADDRESS::Module::name` format, so the kind is
+    /// decided by the item found.
+    pub fn resolve(target: &str, functions: &FnMap, structs: &StructMap) -> Result<Self> {
+        // struct format is a superset of the function one, type arguments are dropped:
+        let addr: StructAddr = target.parse()?;
+        let f = FnAddr::new(addr.addr().to_owned(), addr.name());
+        if !target.contains('<') && functions.contains_key(&f) {
+            Ok(Focus::Function(f))
+        } else if structs.contains_key(&addr) {
+            if target.contains('<') {
+                warn!(
+                    "Type arguments of {} are dropped, the report is focused on all instances of {:#x}",
+                    target.trim(),
+                    addr
+                );
+            }
+            Ok(Focus::Struct(addr))
+        } else {
+            Err(format_err!(
+                "Focus target {:#x} is neither a known function nor a known struct",
+                addr
+            ))
+        }
+    }
+}
+
+/// Drops functions, structs and their derived data unrelated to the `focus`.
+pub fn apply(db: &mut Db, focus: &Focus) {
+    let functions: HashSet<FnAddr> = match focus {
+        Focus::Function(f) => std::iter::once(f.to_owned())
+            .chain(db.call_graph.callees_transitive(f))
+            .collect(),
+        Focus::Struct(s) => db
+            .functions
+            .iter()
+            .filter(|(_, info)| touches(info, s))
+            .map(|(addr, _)| addr.to_owned())
+            .collect(),
+    };
+    let structs: HashSet<StructAddr> = match focus {
+        Focus::Function(_) => used_structs(&db.structs, &db.functions, &functions),
+        Focus::Struct(s) => std::iter::once(s.to_owned()).collect(),
+    };
+    debug!(
+        "focus on {:?}: {} functions, {} structs",
+        focus,
+        functions.len(),
+        structs.len()
+    );
+
+    db.functions.retain(|addr, _| functions.contains(addr));
+    db.structs.retain(|addr, _| structs.contains(addr));
+    db.cfg.retain(|addr, _| functions.contains(addr));
+    db.constants.retain(|module, _| {
+        functions.iter().any(|f| f.addr() == module) || structs.iter().any(|s| s.addr() == module)
+    });
+    db.call_graph = CallGraph::new(&db.functions);
+
+    // modules of the remaining items and their imports:
+    let root: ModAddr = db.root.bc.extract();
+    let modules: HashSet<ModAddr> = std::iter::once(root)
+        .chain(functions.iter().map(|f| f.addr().to_owned()))
+        .chain(structs.iter().map(|s| s.addr().to_owned()))
+        .collect();
+    let imports: HashSet<ModAddr> = extract_mod_handles(&db.root.bc)
+        .into_iter()
+        .chain(
+            modules
+                .iter()
+                .filter_map(|m| db.modules.get(m))
+                .flat_map(|info| info.dependencies().to_vec()),
+        )
+        .chain(modules.iter().cloned())
+        .collect();
+    db.sources
+        .retain(|source| modules.contains(&source.address));
+    db.findings
+        .retain(|finding| modules.contains(&finding.module));
+    db.missed_modules.retain(|m| imports.contains(m));
+    // placeholders of broken files are kept, it's unknown which module they are:
+    db.hidden_modules = db
+        .modules
+        .values()
+        .filter(|info| matches!(info.requested(), Some(m) if !imports.contains(m)))
+        .map(|info| info.mod_addr())
+        .collect();
+    db.root.entry_points = match focus {
+        Focus::Function(f) => vec![f.to_owned()],
+        Focus::Struct(_) => {
+            let mut entry_points = db.root.entry_points.to_owned();
+            entry_points.retain(|f| functions.contains(f));
+            entry_points
+        }
+    };
+}
+
+/// Function packs, unpacks, borrows, accesses in the global storage
+/// or takes the struct as a parameter or a return value.
+fn touches(info: &FunctionInfo, s: &StructAddr) -> bool {
+    let effects = &info.effects;
+    info.struct_ops.contains(s)
+        || info.acquires.contains(s)
+        || effects.reads.contains(s)
+        || effects.mutates.contains(s)
+        || effects.publishes.contains(s)
+        || effects.removes.contains(s)
+        || info
+            .parameters
+            .iter()
+            .chain(info.returns.iter())
+            .any(|ty| mentions(ty, s))
+}
+
+/// Structs used by the `focused` functions along with the structs of their fields.
+fn used_structs(
+    structs: &StructMap,
+    functions: &FnMap,
+    focused: &HashSet<FnAddr>,
+) -> HashSet<StructAddr> {
+    let mut queue = Vec::new();
+    for info in focused.iter().filter_map(|f| functions.get(f)) {
+        let effects = &info.effects;
+        queue.extend(
+            info.struct_ops
+                .packs
+                .iter()
+                .chain(&info.struct_ops.unpacks)
+                .chain(&info.struct_ops.borrows)
+                .chain(&info.acquires)
+                .chain(&effects.reads)
+                .chain(&effects.mutates)
+                .chain(&effects.publishes)
+                .chain(&effects.removes)
+                .cloned(),
+        );
+        for ty in info.parameters.iter().chain(info.returns.iter()) {
+            collect_structs(ty, &mut queue);
+        }
+    }
+
+    let mut used = HashSet::new();
+    while let Some(s) = queue.pop() {
+        if !used.insert(s.to_owned()) {
+            continue;
+        }
+        if let Some(info) = structs.get(&s) {
+            for ty in info.fields.values() {
+                collect_structs(ty, &mut queue);
+            }
+        }
+    }
+    used
+}
+
+fn collect_structs(ty: &Ty, acc: &mut Vec<StructAddr>) {
+    match ty {
+        Ty::Vector(ty) | Ty::Reference(ty) | Ty::MutableReference(ty) => collect_structs(ty, acc),
+        Ty::Struct(s) => acc.push(s.to_owned()),
+        Ty::StructInstantiation(s, args) => {
+            acc.push(s.to_owned());
+            args.iter().for_each(|ty| collect_structs(ty, acc));
+        }
+        _ => {}
+    }
+}
+
+fn mentions(ty: &Ty, s: &StructAddr) -> bool {
+    let mut structs = Vec::new();
+    collect_structs(ty, &mut structs);
+    structs.contains(s)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::fixtures;
+    use super::*;
+
+    fn libra_std() -> (FnMap, StructMap) {
+        let account = fixtures::module("31_LibraAccount");
+        let vector = fixtures::module("5_Vector");
+        let functions = extract_functions(&account, false)
+            .into_iter()
+            .chain(extract_functions(&vector, false))
+            .collect();
+        (functions, extract_struct_map(&account))
+    }
+
+    fn account_fn(name: &str) -> FnAddr {
+        format!("0x1::LibraAccount::{}", name).parse().unwrap()
+    }
+
+    fn account_struct(name: &str) -> StructAddr {
+        format!("0x1::LibraAccount::{}", name).parse().unwrap()
+    }
+
+    #[test]
+    fn resolve() {
+        let (functions, structs) = libra_std();
+        assert_eq!(
+            Focus::resolve("0x1::LibraAccount::pay_from", &functions, &structs).unwrap(),
+            Focus::Function(account_fn("pay_from"))
+        );
+        assert_eq!(
+            Focus::resolve("0x1::LibraAccount::Balance", &functions, &structs).unwrap(),
+            Focus::Struct(account_struct("Balance"))
+        );
+        // type arguments are dropped:
+        assert_eq!(
+            Focus::resolve(
+                "0x1::LibraAccount::Balance<0x1::LBR::LBR>",
+                &functions,
+                &structs
+            )
+            .unwrap(),
+            Focus::Struct(account_struct("Balance"))
+        );
+        assert!(Focus::resolve("0x1::LibraAccount::Missing", &functions, &structs).is_err());
+        assert!(Focus::resolve("0x1::LibraAccount", &functions, &structs).is_err());
+    }
+
+    #[test]
+    fn touches_struct() {
+        let (functions, _) = libra_std();
+        let balance = account_struct("Balance");
+        assert!(touches(&functions[&account_fn("balance")], &balance));
+        assert!(touches(&functions[&account_fn("pay_from")], &balance));
+        assert!(!touches(
+            &functions[&account_fn("sequence_number")],
+            &balance
+        ));
+        assert!(functions
+            .iter()
+            .filter(|(f, _)| f.addr().name() == "Vector")
+            .all(|(_, info)| !touches(info, &balance)));
+    }
+
+    #[test]
+    fn used_structs_with_fields() {
+        let (functions, structs) = libra_std();
+        let focused = std::iter::once(account_fn("pay_from")).collect();
+        let used = used_structs(&structs, &functions, &focused);
+        assert!(used.contains(&account_struct("Balance")));
+        // field of the balance:
+        assert!(used.contains(&"0x1::Libra::Libra".parse().unwrap()));
+
+        let focused = std::iter::once(account_fn("sequence_number")).collect();
+        let used = used_structs(&structs, &functions, &focused);
+        assert!(!used.contains(&account_struct("Balance")));
+    }
+}
@@ -1 +1,8 @@
 pub mod pipeline;
+pub mod call_graph;
+pub mod effects;
+pub mod cfg;
+pub mod verify;
+pub mod upgrade;
+pub mod dialect;
+pub mod focus;
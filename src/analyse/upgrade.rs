@@ -0,0 +1,371 @@
+use serde::Serialize;
+use libra::vm::access::ModuleAccess;
+use libra::vm::file_format::{CompiledModule, StructFieldInformation};
+use crate::extract::prelude::*;
+use crate::types::{extract_ty, IntoModAddr, ModAddr, StructAddr, Ty};
+
+/// Differences between two versions of the same module.
+#[derive(Debug, Clone, Serialize)]
+pub struct UpgradeDiff {
+    pub address: ModAddr,
+    /// `true` if there are no breaking changes.
+    pub compatible: bool,
+    pub changes: Vec<Change>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Change {
+    pub kind: ChangeKind,
+    /// Name of the function or struct.
+    pub item: String,
+    /// Breaks modules and scripts published against the old version.
+    pub breaking: bool,
+    pub details: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Serialize)]
+pub enum ChangeKind {
+    FunctionAdded,
+    FunctionRemoved,
+    FunctionChanged,
+    AcquiresChanged,
+    StructAdded,
+    StructRemoved,
+    StructLayoutChanged,
+    StructKindChanged,
+    StructTypeParamsChanged,
+}
+
+/// Compares public API and struct layouts of the `new` module with the `old` one.
+///
+/// Breaking changes follow Move upgrade rules: public functions can't be removed
+/// or change signature, structs can't be removed or change fields, kind or type parameters.
+/// Private functions and `acquires` are internal to the module, their changes are not breaking.
+pub fn diff_modules(old: &CompiledModule, new: &CompiledModule) -> UpgradeDiff {
+    let mut changes = Vec::new();
+    diff_functions(
+        &extract_functions(old, false),
+        &extract_functions(new, false),
+        &mut changes,
+    );
+    diff_structs(old, new, &mut changes);
+    changes.sort_by(|a, b| a.item.cmp(&b.item));
+
+    UpgradeDiff {
+        address: new.self_id().into_mod_addr(),
+        compatible: !changes.iter().any(|c| c.breaking),
+        changes,
+    }
+}
+
+fn diff_functions(old: &FnMap, new: &FnMap, changes: &mut Vec<Change>) {
+    for (addr, old_fn) in old {
+        let item = addr.name().to_owned();
+        let new_fn = match new.get(addr) {
+            Some(new_fn) => new_fn,
+            None => {
+                changes.push(Change {
+                    kind: ChangeKind::FunctionRemoved,
+                    item,
+                    breaking: old_fn.is_public,
+                    details: None,
+                });
+                continue;
+            }
+        };
+
+        let mut diffs = Vec::new();
+        if old_fn.is_public != new_fn.is_public {
+            diffs.push(if new_fn.is_public {
+                "became public"
+            } else {
+                "became private"
+            });
+        }
+        if old_fn.parameters != new_fn.parameters {
+            diffs.push("parameters changed");
+        }
+        if old_fn.returns != new_fn.returns {
+            diffs.push("returns changed");
+        }
+        if old_fn.type_parameters != new_fn.type_parameters {
+            diffs.push("type parameters changed");
+        }
+        if old_fn.is_native != new_fn.is_native {
+            diffs.push("native flag changed");
+        }
+        if !diffs.is_empty() {
+            // private functions can't be called from outside, only losing public API breaks:
+            changes.push(Change {
+                kind: ChangeKind::FunctionChanged,
+                item: item.to_owned(),
+                breaking: old_fn.is_public,
+                details: Some(diffs.join(", ")),
+            });
+        }
+
+        if old_fn.acquires != new_fn.acquires {
+            changes.push(Change {
+                kind: ChangeKind::AcquiresChanged,
+                item,
+                breaking: false,
+                details: Some(format!(
+                    "{} -> {}",
+                    fmt_structs(&old_fn.acquires),
+                    fmt_structs(&new_fn.acquires)
+                )),
+            });
+        }
+    }
+
+    for (addr, new_fn) in new {
+        if !old.contains_key(addr) {
+            changes.push(Change {
+                kind: ChangeKind::FunctionAdded,
+                item: addr.name().to_owned(),
+                breaking: false,
+                details: if new_fn.is_public {
+                    Some("public".to_owned())
+                } else {
+                    None
+                },
+            });
+        }
+    }
+}
+
+fn diff_structs(old: &CompiledModule, new: &CompiledModule, changes: &mut Vec<Change>) {
+    let old_map = extract_struct_map(old);
+    let new_map = extract_struct_map(new);
+
+    for (addr, old_info) in &old_map {
+        let item = addr.name().to_owned();
+        let new_info = match new_map.get(addr) {
+            Some(info) => info,
+            None => {
+                changes.push(Change {
+                    kind: ChangeKind::StructRemoved,
+                    item,
+                    breaking: true,
+                    details: None,
+                });
+                continue;
+            }
+        };
+
+        if old_info.kind != new_info.kind {
+            changes.push(Change {
+                kind: ChangeKind::StructKindChanged,
+                item: item.to_owned(),
+                breaking: true,
+                details: Some(format!("{:?} -> {:?}", old_info.kind, new_info.kind)),
+            });
+        }
+        if old_info.type_params != new_info.type_params {
+            changes.push(Change {
+                kind: ChangeKind::StructTypeParamsChanged,
+                item: item.to_owned(),
+                breaking: true,
+                details: Some(format!(
+                    "{:?} -> {:?}",
+                    old_info.type_params, new_info.type_params
+                )),
+            });
+        }
+
+        // field order defines the layout in the storage, so compare declared fields:
+        let old_fields = struct_layout(old, addr);
+        let new_fields = struct_layout(new, addr);
+        if old_fields != new_fields {
+            changes.push(Change {
+                kind: ChangeKind::StructLayoutChanged,
+                item,
+                breaking: true,
+                details: None,
+            });
+        }
+    }
+
+    for addr in new_map.keys() {
+        if !old_map.contains_key(addr) {
+            changes.push(Change {
+                kind: ChangeKind::StructAdded,
+                item: addr.name().to_owned(),
+                breaking: false,
+                details: None,
+            });
+        }
+    }
+}
+
+/// Declared fields of the struct in order, `None` for native structs.
+fn struct_layout(compiled_mod: &CompiledModule, addr: &StructAddr) -> Option<Vec<(String, Ty)>> {
+    let def = compiled_mod.struct_defs().iter().find(|def| {
+        let handle = compiled_mod.struct_handle_at(def.struct_handle);
+        compiled_mod.identifier_at(handle.name).as_str() == addr.name()
+    })?;
+    match &def.field_information {
+        StructFieldInformation::Native => None,
+        StructFieldInformation::Declared(fields) => Some(
+            fields
+                .iter()
+                .map(|field| {
+                    (
+                        compiled_mod.identifier_at(field.name).to_string(),
+                        extract_ty(&field.signature.0, compiled_mod),
+                    )
+                })
+                .collect(),
+        ),
+    }
+}
+
+fn fmt_structs(structs: &[StructAddr]) -> String {
+    let names: Vec<_> = structs.iter().map(|s| s.name()).collect();
+    format!("[{}]", names.join(", "))
+}
+
+#[cfg(test)]
+mod tests {
+    use libra::vm::file_format::{CompiledModuleMut, Kind};
+    use crate::fixtures;
+    use super::*;
+
+    fn account() -> CompiledModule {
+        fixtures::module("31_LibraAccount")
+    }
+
+    fn mutate<F: FnOnce(&mut CompiledModuleMut)>(f: F) -> CompiledModule {
+        let mut inner = account().into_inner();
+        f(&mut inner);
+        inner.freeze().unwrap()
+    }
+
+    fn find(diff: &UpgradeDiff, kind: ChangeKind) -> &Change {
+        diff.changes
+            .iter()
+            .find(|c| c.kind == kind)
+            .unwrap_or_else(|| panic!("{:?} not found in {:?}", kind, diff.changes))
+    }
+
+    #[test]
+    fn same_module() {
+        let diff = diff_modules(&account(), &account());
+        assert!(diff.compatible);
+        assert!(diff.changes.is_empty(), "{:?}", diff.changes);
+    }
+
+    #[test]
+    fn public_function_removed_and_added() {
+        let removed = mutate(|m| {
+            let idx = m
+                .function_defs
+                .iter()
+                .position(|def| def.is_public())
+                .unwrap();
+            m.function_defs.remove(idx);
+        });
+
+        let diff = diff_modules(&account(), &removed);
+        assert!(!diff.compatible);
+        assert_eq!(1, diff.changes.len(), "{:?}", diff.changes);
+        assert!(find(&diff, ChangeKind::FunctionRemoved).breaking);
+
+        let diff = diff_modules(&removed, &account());
+        assert!(diff.compatible);
+        let added = find(&diff, ChangeKind::FunctionAdded);
+        assert!(!added.breaking);
+        assert_eq!(Some("public"), added.details.as_deref());
+    }
+
+    #[test]
+    fn acquires_changed() {
+        let new = mutate(|m| {
+            let def = m
+                .function_defs
+                .iter_mut()
+                .find(|def| !def.acquires_global_resources.is_empty())
+                .unwrap();
+            def.acquires_global_resources.clear();
+        });
+
+        let diff = diff_modules(&account(), &new);
+        assert!(diff.compatible);
+        assert_eq!(1, diff.changes.len(), "{:?}", diff.changes);
+        let change = find(&diff, ChangeKind::AcquiresChanged);
+        assert!(!change.breaking);
+        assert!(change.details.as_deref().unwrap().ends_with("-> []"));
+    }
+
+    #[test]
+    fn struct_layout_changed() {
+        let new = mutate(|m| {
+            let fields = m
+                .struct_defs
+                .iter_mut()
+                .find_map(|def| match &mut def.field_information {
+                    StructFieldInformation::Declared(fields) if fields.len() > 1 => Some(fields),
+                    _ => None,
+                })
+                .unwrap();
+            fields.swap(0, 1);
+        });
+
+        let diff = diff_modules(&account(), &new);
+        assert!(!diff.compatible);
+        assert_eq!(1, diff.changes.len(), "{:?}", diff.changes);
+        assert!(find(&diff, ChangeKind::StructLayoutChanged).breaking);
+    }
+
+    #[test]
+    fn struct_kind_changed() {
+        let new = mutate(|m| {
+            let self_handle = m.self_module_handle_idx;
+            let handle = m
+                .struct_handles
+                .iter_mut()
+                .find(|h| h.module == self_handle && h.is_nominal_resource)
+                .unwrap();
+            handle.is_nominal_resource = false;
+        });
+
+        let diff = diff_modules(&account(), &new);
+        assert!(!diff.compatible);
+        let change = find(&diff, ChangeKind::StructKindChanged);
+        assert!(change.breaking);
+        assert!(change
+            .details
+            .as_deref()
+            .unwrap()
+            .starts_with("Resource -> "));
+    }
+
+    #[test]
+    fn struct_type_params_changed() {
+        let new = mutate(|m| {
+            let self_handle = m.self_module_handle_idx;
+            let handle = m
+                .struct_handles
+                .iter_mut()
+                .find(|h| h.module == self_handle && !h.type_parameters.is_empty())
+                .unwrap();
+            // the number of parameters is bound by instantiations, so the kind is changed:
+            handle.type_parameters[0] = match handle.type_parameters[0] {
+                Kind::Resource => Kind::Copyable,
+                _ => Kind::Resource,
+            };
+        });
+
+        let diff = diff_modules(&account(), &new);
+        assert!(!diff.compatible);
+        assert!(diff
+            .changes
+            .iter()
+            .all(|c| c.kind != ChangeKind::StructKindChanged));
+        let change = find(&diff, ChangeKind::StructTypeParamsChanged);
+        assert!(change.breaking);
+        let details = change.details.as_deref().unwrap();
+        let (old_params, new_params) = details.split_at(details.find(" -> ").unwrap());
+        assert_ne!(old_params, &new_params[" -> ".len()..]);
+    }
+}
@@ -0,0 +1,166 @@
+use serde::Serialize;
+use libra::bytecode_verifier::{dependencies, verifier};
+use libra::vm::access::ModuleAccess;
+use libra::vm::errors::VMError;
+use libra::vm::file_format::{CodeOffset, CompiledModule, CompiledScript, FunctionDefinitionIndex};
+use crate::disasm::{default_script_fn_address, CompiledMoveRef};
+use crate::deps::map::{ModInfo, ModMap};
+use crate::extract::prelude::*;
+use crate::types::{FnAddr, ModAddr};
+
+/// Error reported by the bytecode verifier.
+#[derive(Debug, Clone, Serialize)]
+pub struct Finding {
+    pub module: ModAddr,
+    pub stage: VerifyStage,
+    /// Status code of the error, e.g. `NEGATIVE_STACK_SIZE_WITHIN_BLOCK`.
+    pub status: String,
+    pub message: Option<String>,
+    pub function: Option<FnAddr>,
+    pub offset: Option<CodeOffset>,
+}
+
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Serialize)]
+pub enum VerifyStage {
+    /// Checks of the bytecode itself.
+    Bytecode,
+    /// Checks of the imports against the dependencies.
+    Linking,
+}
+
+/// Runs the verifier on the module and links it against the resolved dependencies.
+pub fn verify_module(module: &CompiledModule, deps: &ModMap) -> Vec<Finding> {
+    let addr: ModAddr = module.self_id().into_mod_addr();
+    let fn_addr = |idx: FunctionDefinitionIndex| {
+        let def = module.function_defs().get(idx.0 as usize)?;
+        let handle = module.function_handle_at(def.function);
+        Some(FnAddr::new(addr.clone(), module.identifier_at(handle.name)))
+    };
+
+    let bytecode = verifier::verify_module(module).err();
+    let linking = dependencies::verify_module(
+        module,
+        deps.values()
+            .filter_map(ModInfo::bytecode)
+            .filter(|dep| dep.self_id() != module.self_id()),
+    )
+    .err();
+
+    findings(&addr, bytecode, linking, fn_addr)
+}
+
+/// Runs the verifier on the script and links it against the resolved dependencies.
+pub fn verify_script(script: &CompiledScript, deps: &ModMap) -> Vec<Finding> {
+    let addr: ModAddr = CompiledMoveRef::from(script).extract();
+    let bytecode = verifier::verify_script(script).err();
+    let linking =
+        dependencies::verify_script(script, deps.values().filter_map(ModInfo::bytecode)).err();
+
+    findings(&addr, bytecode, linking, |_| {
+        Some(default_script_fn_address().into())
+    })
+}
+
+fn findings<F>(
+    module: &ModAddr,
+    bytecode: Option<VMError>,
+    linking: Option<VMError>,
+    fn_addr: F,
+) -> Vec<Finding>
+where
+    F: Fn(FunctionDefinitionIndex) -> Option<FnAddr>,
+{
+    let errors = bytecode
+        .map(|err| (VerifyStage::Bytecode, err))
+        .into_iter()
+        .chain(linking.map(|err| (VerifyStage::Linking, err)));
+
+    errors
+        .map(|(stage, err)| {
+            let (function, offset) = match err.offsets().first() {
+                Some((idx, offset)) => (fn_addr(*idx), Some(*offset)),
+                None => (None, None),
+            };
+            Finding {
+                module: module.to_owned(),
+                stage,
+                status: format!("{:?}", err.major_status()),
+                message: err.message().cloned(),
+                function,
+                offset,
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+    use libra::vm::file_format::Bytecode;
+    use crate::deps::map::DependencyMap;
+    use crate::fixtures;
+    use super::*;
+
+    fn libra_std() -> ModMap {
+        let mut map = ModMap::default();
+        for name in fixtures::names("modules") {
+            map.insert_mod(PathBuf::from(&name), fixtures::module(&name));
+        }
+        map
+    }
+
+    #[test]
+    fn valid_module() {
+        let module = fixtures::module("31_LibraAccount");
+        assert!(verify_module(&module, &libra_std()).is_empty());
+    }
+
+    #[test]
+    fn valid_script() {
+        let script = fixtures::script("peer_to_peer_with_metadata");
+        assert!(verify_script(&script, &libra_std()).is_empty());
+    }
+
+    #[test]
+    fn missing_dependency() {
+        let module = fixtures::module("31_LibraAccount");
+        let findings = verify_module(&module, &ModMap::default());
+        assert_eq!(1, findings.len());
+        assert_eq!(VerifyStage::Linking, findings[0].stage);
+        assert_eq!("MISSING_DEPENDENCY", findings[0].status);
+    }
+
+    #[test]
+    fn corrupted_module() {
+        let mut inner = fixtures::module("31_LibraAccount").into_inner();
+        // a body without branches keeps valid offsets after the insertion:
+        let def = inner
+            .function_defs
+            .iter_mut()
+            .find(|def| match &def.code {
+                Some(code) => !code.code.iter().any(|op| {
+                    matches!(
+                        op,
+                        Bytecode::Branch(_) | Bytecode::BrTrue(_) | Bytecode::BrFalse(_)
+                    )
+                }),
+                None => false,
+            })
+            .unwrap();
+        def.code.as_mut().unwrap().code.insert(0, Bytecode::Pop);
+        let handle = def.function;
+        let module = inner.freeze().unwrap();
+        let name = module.identifier_at(module.function_handle_at(handle).name);
+
+        let findings = verify_module(&module, &libra_std());
+        assert_eq!(1, findings.len(), "{:?}", findings);
+        let finding = &findings[0];
+        assert_eq!(VerifyStage::Bytecode, finding.stage);
+        assert_eq!("NEGATIVE_STACK_SIZE_WITHIN_BLOCK", finding.status);
+        assert_eq!(
+            Some(FnAddr::new(module.self_id().into_mod_addr(), name)),
+            finding.function
+        );
+        assert_eq!(Some(0), finding.offset);
+    }
+}
@@ -0,0 +1,324 @@
+use std::path::{Path, PathBuf};
+use anyhow::{format_err, Result};
+use clap::ArgMatches;
+use serde::Deserialize;
+use crate::output::utils::path_to_string;
+use super::Opts;
+
+/// Config file looked up in the current directory if `--config` isn't passed.
+pub const DEFAULT_CONFIG_FILE: &str = "unscrambler.toml";
+
+/// Project-level configuration, e.g.:
+///
+/// ```toml
+/// dialect = "dfi"
+///
+/// [input]
+/// path = "target/modules/0_Coins.mv"
+/// dependencies = ["target/deps"]
+/// recursive = true
+///
+/// [output]
+/// dir = "report"
+/// format = "html"
+///
+/// [analysis]
+/// decompile = true
+/// focus = "0x1::Coins::Balance"
+/// ```
+///
+/// Paths are relative to the config file. Values passed in the command line take precedence.
+/// Boolean flags can only turn options on: the command line can't turn off an option enabled
+/// in the config, e.g. `recursive = true` stays enabled without `--recursive`.
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    pub dialect: Option<String>,
+    pub input: InputConfig,
+    pub output: OutputConfig,
+    pub analysis: AnalysisConfig,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields, rename_all = "kebab-case")]
+pub struct InputConfig {
+    pub path: Option<PathBuf>,
+    pub kind: Option<String>,
+    pub dependencies: Vec<PathBuf>,
+    pub recursive: Option<bool>,
+    pub follow_symlinks: Option<bool>,
+    pub source_map: Option<PathBuf>,
+    pub upgrade_from: Option<PathBuf>,
+    pub offline: Option<bool>,
+    pub data_sources: Vec<String>,
+    pub cache_dir: Option<PathBuf>,
+    pub no_cache: Option<bool>,
+    pub jobs: Option<usize>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct OutputConfig {
+    pub dir: Option<PathBuf>,
+    pub format: Option<String>,
+    pub force: Option<bool>,
+    pub inline: Option<bool>,
+    pub templates: Option<PathBuf>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct AnalysisConfig {
+    pub decompile: Option<bool>,
+    pub focus: Option<String>,
+}
+
+impl Config {
+    pub fn load(path: &Path) -> Result<Self> {
+        let src = std::fs::read_to_string(path).map_err(|err| {
+            format_err!("Unable to read config {}: {}", path_to_string(path), err)
+        })?;
+        let mut config: Config = toml::from_str(&src)
+            .map_err(|err| format_err!("Invalid config {}: {}", path_to_string(path), err))?;
+        if let Some(base) = path.parent() {
+            config.rebase(base);
+        }
+        Ok(config)
+    }
+
+    /// Makes relative paths relative to the `base` directory.
+    fn rebase(&mut self, base: &Path) {
+        let rebase = |p: &mut PathBuf| {
+            if p.is_relative() {
+                *p = base.join(&p);
+            }
+        };
+        let input = &mut self.input;
+        input.path.iter_mut().for_each(rebase);
+        input.dependencies.iter_mut().for_each(rebase);
+        input.source_map.iter_mut().for_each(rebase);
+        input.upgrade_from.iter_mut().for_each(rebase);
+        input.cache_dir.iter_mut().for_each(rebase);
+        self.output.dir.iter_mut().for_each(rebase);
+        self.output.templates.iter_mut().for_each(rebase);
+    }
+}
+
+/// Loads the config requested by `--config` or the default one if it exists
+/// and fills options which aren't passed in the command line.
+pub fn apply(opts: &mut Opts, matches: &ArgMatches) -> Result<()> {
+    let path = match &opts.config {
+        Some(path) => path.to_owned(),
+        None => {
+            let path = PathBuf::from(DEFAULT_CONFIG_FILE);
+            if !path.is_file() {
+                return Ok(());
+            }
+            path
+        }
+    };
+    debug!("loading config {}", path_to_string(&path));
+    let config = Config::load(&path)?;
+    merge(opts, config, |arg| matches.occurrences_of(arg) > 0)
+}
+
+fn merge<F: Fn(&str) -> bool>(opts: &mut Opts, config: Config, is_passed: F) -> Result<()> {
+    let input = &mut opts.input;
+    let output = &mut opts.output;
+    let Config {
+        dialect,
+        input: input_cfg,
+        output: output_cfg,
+        analysis,
+    } = config;
+
+    if let Some(dialect) = dialect.filter(|_| input.dialect.is_none()) {
+        input.dialect = Some(dialect.parse().map_err(anyhow::Error::msg)?);
+    }
+
+    let fs = &mut input.offline;
+    if fs.path.is_none() {
+        fs.path = input_cfg.path;
+    }
+    if let Some(kind) = input_cfg.kind.filter(|_| !is_passed("kind")) {
+        fs.kind = kind.parse().map_err(anyhow::Error::msg)?;
+    }
+    if fs.dependencies.is_empty() {
+        fs.dependencies = input_cfg.dependencies;
+    }
+    fs.search_recursive |= input_cfg.recursive.unwrap_or_default();
+    fs.follow_symlinks |= input_cfg.follow_symlinks.unwrap_or_default();
+    if fs.source_map.is_none() {
+        fs.source_map = input_cfg.source_map;
+    }
+    if fs.upgrade_from.is_none() {
+        fs.upgrade_from = input_cfg.upgrade_from;
+    }
+
+    let net = &mut input.online;
+    net.offline |= input_cfg.offline.unwrap_or_default();
+    if net.ds.is_empty() {
+        net.ds = input_cfg.data_sources;
+    }
+    if net.cache_dir.is_none() {
+        net.cache_dir = input_cfg.cache_dir;
+    }
+    net.no_cache |= input_cfg.no_cache.unwrap_or_default();
+    if let Some(jobs) = input_cfg.jobs.filter(|_| !is_passed("jobs")) {
+        net.jobs = Some(jobs);
+    }
+
+    if let Some(dir) = output_cfg.dir.filter(|_| !is_passed("output directory")) {
+        output.dir = dir;
+    }
+    if let Some(format) = output_cfg.format.filter(|_| !is_passed("format")) {
+        output.format = format.parse().map_err(anyhow::Error::msg)?;
+    }
+    output.force |= output_cfg.force.unwrap_or_default();
+    output.inline |= output_cfg.inline.unwrap_or_default();
+    if output.templates.is_none() {
+        output.templates = output_cfg.templates;
+    }
+    output.decompile |= analysis.decompile.unwrap_or_default();
+    if output.focus.is_none() {
+        output.focus = analysis.focus;
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use clap::{FromArgMatches, IntoApp};
+    use crate::cli::{Dialect, OutputFmt};
+    use super::*;
+
+    fn merged(args: &[&str], config: &str) -> Opts {
+        let args = std::iter::once("move-unscrambler").chain(args.iter().copied());
+        let matches = Opts::into_app().get_matches_from(args);
+        let mut opts = Opts::from_arg_matches(&matches);
+        let config: Config = toml::from_str(config).unwrap();
+        merge(&mut opts, config, |arg| matches.occurrences_of(arg) > 0).unwrap();
+        opts
+    }
+
+    #[test]
+    fn rebase() {
+        let mut config: Config = toml::from_str(
+            r#"
+            [input]
+            path = "modules/Coins.mv"
+            dependencies = ["deps", "/abs/deps"]
+            [output]
+            dir = "report"
+            "#,
+        )
+        .unwrap();
+        config.rebase(Path::new("/project"));
+        assert_eq!(
+            Some(PathBuf::from("/project/modules/Coins.mv")),
+            config.input.path
+        );
+        assert_eq!(
+            vec![PathBuf::from("/project/deps"), PathBuf::from("/abs/deps")],
+            config.input.dependencies
+        );
+        assert_eq!(Some(PathBuf::from("/project/report")), config.output.dir);
+    }
+
+    #[test]
+    fn unknown_fields() {
+        assert!(toml::from_str::<Config>("[input]\nunknown = 1").is_err());
+    }
+
+    #[test]
+    fn config_fills_missing() {
+        let opts = merged(
+            &[],
+            r#"
+            dialect = "dfi"
+            [input]
+            path = "Coins.mv"
+            dependencies = ["deps"]
+            recursive = true
+            data-sources = ["http://node"]
+            jobs = 2
+            [output]
+            dir = "report"
+            format = "html"
+            [analysis]
+            decompile = true
+            focus = "0x1::Coins::Balance"
+            "#,
+        );
+        assert_eq!(Some(Dialect::Dfinance), opts.input.dialect);
+        assert_eq!(Some(PathBuf::from("Coins.mv")), opts.input.offline.path);
+        assert_eq!(vec![PathBuf::from("deps")], opts.input.offline.dependencies);
+        assert!(opts.input.offline.search_recursive);
+        assert_eq!(vec!["http://node".to_owned()], opts.input.online.ds);
+        assert_eq!(2, opts.input.online.jobs());
+        assert_eq!(PathBuf::from("report"), opts.output.dir);
+        assert!(matches!(opts.output.format, OutputFmt::Html));
+        assert!(opts.output.decompile);
+        assert_eq!(Some("0x1::Coins::Balance"), opts.output.focus.as_deref());
+    }
+
+    #[test]
+    fn command_line_wins() {
+        let opts = merged(
+            &[
+                "--dialect",
+                "libra",
+                "-i",
+                "Other.mv",
+                "-d",
+                "other",
+                "--jobs",
+                "4",
+                "-o",
+                "out",
+                "--fmt",
+                "json",
+            ],
+            r#"
+            dialect = "dfi"
+            [input]
+            path = "Coins.mv"
+            dependencies = ["deps"]
+            jobs = 2
+            [output]
+            dir = "report"
+            format = "html"
+            "#,
+        );
+        assert_eq!(Some(Dialect::Libra), opts.input.dialect);
+        assert_eq!(Some(PathBuf::from("Other.mv")), opts.input.offline.path);
+        assert_eq!(
+            vec![PathBuf::from("other")],
+            opts.input.offline.dependencies
+        );
+        assert_eq!(4, opts.input.online.jobs());
+        assert_eq!(PathBuf::from("out"), opts.output.dir);
+        assert!(matches!(opts.output.format, OutputFmt::Json));
+    }
+
+    #[test]
+    fn flags_only_add() {
+        let opts = merged(&["--inline"], "[output]\nforce = true");
+        assert!(opts.output.inline);
+        assert!(opts.output.force);
+
+        let opts = merged(&[], "[output]\nforce = false");
+        assert!(!opts.output.force);
+    }
+
+    #[test]
+    fn default_jobs() {
+        let opts = merged(&[], "");
+        assert_eq!(None, opts.input.online.jobs);
+        assert_eq!(
+            crate::deps::resolver::DEFAULT_JOBS,
+            opts.input.online.jobs()
+        );
+    }
+}
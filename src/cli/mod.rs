@@ -1,17 +1,21 @@
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use anyhow::{format_err, Result};
 pub use clap::Clap;
+use clap::{FromArgMatches, IntoApp};
+use crate::deps::cache::DependencyCache;
+use crate::deps::resolver::DEFAULT_JOBS;
 
 mod logging;
+pub mod config;
 
 #[derive(Clap, Debug)]
 #[clap(name = "Move Unscrambler")]
 pub struct Opts {
-    // /// Sets a custom config file. Could have been an Option<T> with no default too
-    // #[clap(short, long, default_value = "~/.somedir/default.toml")]
-    // config: String,
-    //
-    //
+    /// Config file with default options.
+    /// `unscrambler.toml` in the current directory is used if exists.
+    #[clap(short = "c", long = "config", name = "config file")]
+    pub config: Option<PathBuf>,
+
     #[clap(flatten)]
     pub input: Input,
 
@@ -24,7 +28,7 @@ pub struct Opts {
 
 pub const OUTPUT_STDOUT: &'static str = "--";
 
-#[derive(Clap, Debug)]
+#[derive(Clap, Debug, Clone)]
 pub struct Output {
     /// Output target directory.
     /// Prints to stdout by default.
@@ -41,11 +45,44 @@ pub struct Output {
 
     /// Inlines assets into the output document.
     /// Default value is false, but forced to true if output setted up to stdout (--).
+    /// HTML reports still load mermaid.js from the CDN to render CFG diagrams,
+    /// so they need network access to show them.
     #[clap(long, name = "inline assets")]
     pub inline: bool,
+
+    /// Handlebars templates directory.
+    /// Every `<name>.hbs.<fmt>` file overrides built-in template or partial with the same name,
+    /// e.g. `function.hbs.md` or `document.hbs.html`.
+    #[clap(long = "templates", name = "templates directory")]
+    pub templates: Option<PathBuf>,
+
+    /// Adds decompiled Move source of the input and its dependencies to the report.
+    /// Sources are also saved as `.move` files unless assets are inlined.
+    #[clap(long)]
+    pub decompile: bool,
+
+    /// Restricts the report to a single function and everything it transitively calls
+    /// or a single struct and every function that packs, unpacks, borrows or publishes it.
+    /// Format: `ADDRESS::Module::name`.
+    #[clap(long = "focus", name = "function or struct")]
+    pub focus: Option<String>,
 }
 
-#[derive(Clap, Debug)]
+impl Output {
+    pub fn is_stdout(&self) -> bool {
+        self.dir.as_os_str().to_str() == Some(OUTPUT_STDOUT)
+    }
+
+    /// Same options with the output into the `name` subdirectory.
+    pub fn nested(&self, name: &str) -> Self {
+        Self {
+            dir: self.dir.join(name),
+            ..self.clone()
+        }
+    }
+}
+
+#[derive(Clap, Debug, Clone)]
 pub enum OutputFmt {
     Markdown,
     Html,
@@ -70,6 +107,7 @@ impl std::str::FromStr for OutputFmt {
             Self::MARKDOWN => Ok(OutputFmt::Markdown),
             Self::HTML => Ok(OutputFmt::Html),
             Self::JSON => Ok(OutputFmt::Json),
+            Self::YAML => Ok(OutputFmt::Yaml),
             _ => Err(format!("Unsupported output format '{}'", s)),
         }
     }
@@ -84,15 +122,18 @@ pub struct Input {
     pub offline: InputFs,
 
     /// Sets Move implementation.
-    #[clap(long, possible_values = &Dialect::ALL_OPTIONS, default_value = Dialect::DEFAULT)]
-    pub dialect: Dialect,
+    /// Inferred from the bytecode if not passed.
+    #[clap(long, possible_values = &Dialect::ALL_OPTIONS)]
+    pub dialect: Option<Dialect>,
 }
 
 #[derive(Clap, Debug)]
 pub struct InputFs {
-    /// Input bytecode-file path.
+    /// Input bytecode-file path, directory or glob pattern (quoted) of `.mv` files.
+    /// Every file of a batch gets own report next to the index page.
+    /// Required unless it's set in the config.
     #[clap(short = "i", long = "input", name = "input file")]
-    pub path: PathBuf,
+    pub path: Option<PathBuf>,
 
     /// Sets type for main input bytecode-file to script or module.
     #[clap(long, possible_values = &InputType::ALL_OPTIONS, default_value = InputType::DEFAULT)]
@@ -110,6 +151,23 @@ pub struct InputFs {
     /// Follow symbolic links when search dependencies.
     #[clap(long = "follow-symlinks")]
     pub follow_symlinks: bool,
+
+    /// Source map (`.mvsm`) of the input script.
+    /// Used to show original names of the script parameters.
+    #[clap(long = "source-map", name = "source map file")]
+    pub source_map: Option<PathBuf>,
+
+    /// Previous version of the input module.
+    /// Adds upgrade compatibility of the input against it to the report.
+    #[clap(long = "upgrade-from", name = "previous module file")]
+    pub upgrade_from: Option<PathBuf>,
+}
+
+impl InputFs {
+    /// Path of the input, presence is checked on config validation.
+    pub fn input_path(&self) -> &Path {
+        self.path.as_deref().expect("Input path is validated")
+    }
 }
 
 #[derive(Clap, Debug)]
@@ -125,6 +183,49 @@ pub struct InputNet {
     /// Can be disabled by pass --offline flag.
     #[clap(long = "data-source", name = "URI")]
     pub ds: Vec<String /* TODO: use http::Uri */>,
+
+    /// Directory of the cache of modules fetched from data-sources.
+    /// User's cache directory is used by default.
+    /// In offline mode dependencies are still resolved from the cache.
+    #[clap(long = "cache-dir", name = "cache directory")]
+    pub cache_dir: Option<PathBuf>,
+
+    /// Max number of concurrent requests to data-sources.
+    /// Resolver's default is used unless it's set.
+    #[clap(long)]
+    pub jobs: Option<usize>,
+
+    /// Disables the cache of fetched modules.
+    #[clap(long = "no-cache")]
+    pub no_cache: bool,
+
+    /// Fetches all modules again and overwrites cached ones.
+    #[clap(long = "refresh-cache")]
+    pub refresh_cache: bool,
+
+    /// Removes all cached modules before the run.
+    /// Input file can be omitted to purge the cache only.
+    #[clap(long = "purge-cache")]
+    pub purge_cache: bool,
+}
+
+impl InputNet {
+    /// Max number of concurrent requests, the resolver's default unless it's set.
+    pub fn jobs(&self) -> usize {
+        self.jobs.unwrap_or(DEFAULT_JOBS)
+    }
+
+    /// Cache of fetched modules unless it's disabled.
+    pub fn cache(&self) -> Option<DependencyCache> {
+        if self.no_cache {
+            return None;
+        }
+        let dir = self
+            .cache_dir
+            .clone()
+            .unwrap_or_else(DependencyCache::default_dir);
+        Some(DependencyCache::new(dir))
+    }
 }
 
 #[derive(Clap, Debug)]
@@ -155,20 +256,26 @@ impl std::str::FromStr for InputType {
     }
 }
 
-#[derive(Clap, Copy, Clone, Debug)]
+#[derive(Clap, Copy, Clone, Debug, Eq, PartialEq, Hash)]
 pub enum Dialect {
     Dfinance,
     Libra,
 }
 
 impl Dialect {
-    // TODO: set DEFAULT to Self::DFINANCE.
-    const DEFAULT: &'static str = Self::LIBRA;
     const DFINANCE: &'static str = "dfi";
     const LIBRA: &'static str = "libra";
     const ALL_OPTIONS: [&'static str; 2] = [Self::DFINANCE, Self::LIBRA];
 }
 
+impl Default for Dialect {
+    /// Used if the dialect is neither passed nor inferred.
+    // TODO: set default to Dfinance.
+    fn default() -> Self {
+        Self::Libra
+    }
+}
+
 impl std::str::FromStr for Dialect {
     type Err = String;
 
@@ -183,14 +290,20 @@ impl std::str::FromStr for Dialect {
 
 #[allow(dead_code)]
 pub fn try_init() -> Result<Opts> {
-    let opts: Opts = Opts::try_parse().map_err(|err| anyhow!("{}", err))?;
+    let matches = Opts::into_app()
+        .try_get_matches()
+        .map_err(|err| anyhow!("{}", err))?;
+    let mut opts = Opts::from_arg_matches(&matches);
     logging::try_init(opts.log.verbose).map_err(|err| anyhow!("{}", err))?;
+    config::apply(&mut opts, &matches)?;
     Ok(opts)
 }
 
 #[allow(dead_code)]
-pub fn init() -> Opts {
-    let opts: Opts = Opts::parse();
+pub fn init() -> Result<Opts> {
+    let matches = Opts::into_app().get_matches();
+    let mut opts = Opts::from_arg_matches(&matches);
     logging::init(opts.log.verbose);
-    opts
+    config::apply(&mut opts, &matches)?;
+    Ok(opts)
 }
@@ -4,21 +4,34 @@ use crate::cli::Dialect;
 use crate::extract::prelude::*;
 use crate::disasm::{CompiledMoveRef, CompiledMove};
 use crate::{
-    output::ctx::{ContextRoot, Context, StructKnowledge, FnKnowledge, FnKnowledgeBasic},
+    analyse::{call_graph::CallGraph, cfg::CfgMap, upgrade::UpgradeDiff, verify::Finding},
+    output::ctx::{
+        ContextRoot, Context, StructKnowledge, FnKnowledge, FnKnowledgeBasic, SourceEntry,
+    },
     deps::map::ModMap,
 };
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
 
 /// Storage for intermediate results
 pub struct Db {
     pub dialect: Dialect,
     pub root: DbRoot,
 
-    pub modules: ModMap,
+    /// Dependencies shared by all inputs of the batch.
+    pub modules: Arc<ModMap>,
     pub functions: FnMap,
     pub structs: StructMap,
+    pub call_graph: CallGraph,
+    pub cfg: CfgMap,
+    pub constants: ConstMap,
+    pub sources: Vec<SourceEntry>,
+    pub findings: Vec<Finding>,
+    pub upgrade: Option<UpgradeDiff>,
 
     pub missed_modules: Vec<ModAddr>,
+    /// Broken dependencies unrelated to the focus, they aren't reported.
+    pub hidden_modules: HashSet<ModAddr>,
 }
 
 pub struct DbRoot {
@@ -53,7 +66,7 @@ impl ExtractRef<DbRoot> for Db {
 
 impl ExtractRef<ModMap> for Db {
     fn extract_ref(&self) -> &ModMap {
-        &self.modules
+        &*self.modules
     }
 }
 
@@ -63,6 +76,42 @@ impl ExtractRef<FnMap> for Db {
     }
 }
 
+impl ExtractRef<CallGraph> for Db {
+    fn extract_ref(&self) -> &CallGraph {
+        &self.call_graph
+    }
+}
+
+impl ExtractRef<CfgMap> for Db {
+    fn extract_ref(&self) -> &CfgMap {
+        &self.cfg
+    }
+}
+
+impl ExtractRef<ConstMap> for Db {
+    fn extract_ref(&self) -> &ConstMap {
+        &self.constants
+    }
+}
+
+impl ExtractRef<[Finding]> for Db {
+    fn extract_ref(&self) -> &[Finding] {
+        &self.findings
+    }
+}
+
+impl ExtractRef<Option<UpgradeDiff>> for Db {
+    fn extract_ref(&self) -> &Option<UpgradeDiff> {
+        &self.upgrade
+    }
+}
+
+impl ExtractRef<[SourceEntry]> for Db {
+    fn extract_ref(&self) -> &[SourceEntry] {
+        &self.sources
+    }
+}
+
 impl Extract<StructMap> for Db {
     fn extract(&self) -> StructMap {
         self.structs.to_owned()
@@ -79,6 +128,9 @@ impl Context<StructInfo> for Db {
     fn root(&self) -> &DbRoot {
         &self.root
     }
+    fn is_reported(&self, module: &ModAddr) -> bool {
+        !self.hidden_modules.contains(module)
+    }
 }
 
 impl ContextRoot for DbRoot {
@@ -116,11 +168,22 @@ impl Extract<FnKnowledgeBasic> for (&FnAddr, &FunctionInfo) {
         FnKnowledgeBasic {
             address: self.0.to_owned(),
             parameters: self.1.parameters.clone(),
+            parameter_names: if self.1.parameter_names.len() == self.1.parameters.len() {
+                self.1.parameter_names.clone()
+            } else {
+                (0..self.1.parameters.len())
+                    .map(|i| format!("arg{}", i))
+                    .collect()
+            },
             type_parameters: self.1.type_parameters.clone(),
             returns: self.1.returns.clone(),
             acquires: self.1.acquires.clone(),
             is_public: self.1.is_public,
             is_native: self.1.is_native,
+            calls: self.1.calls.clone(),
+            callers: Default::default(),
+            cfg: Default::default(),
+            aborts: self.1.aborts.clone(),
             // code:
         }
     }
@@ -0,0 +1,218 @@
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::{SystemTime, UNIX_EPOCH};
+use anyhow::{format_err, Result};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use crate::output::utils::path_to_string;
+use crate::output::writer::write_atomic;
+use crate::types::ModAddr;
+use super::DependencySource;
+use super::DependencySearch;
+
+const CACHE_DIR_NAME: &str = "move-unscrambler";
+const BYTECODE_EXT: &str = "mv";
+const META_EXT: &str = "json";
+
+/// Local storage of modules fetched from data-sources.
+///
+/// Every entry is addressed by the hash of the data-source URI and the module address,
+/// so the same module fetched from different nodes is stored separately.
+pub struct DependencyCache {
+    dir: PathBuf,
+}
+
+/// Description of the cached module stored next to its bytecode.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CacheMeta {
+    pub uri: String,
+    pub module: String,
+    /// Unix timestamp of the fetch in seconds.
+    pub fetched: u64,
+}
+
+impl DependencyCache {
+    pub fn new<P: Into<PathBuf>>(dir: P) -> Self {
+        Self { dir: dir.into() }
+    }
+
+    /// User's cache directory, system temp dir if it's unknown.
+    pub fn default_dir() -> PathBuf {
+        dirs::cache_dir()
+            .unwrap_or_else(std::env::temp_dir)
+            .join(CACHE_DIR_NAME)
+    }
+
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    fn entry_path(&self, uri: &str, module: &ModAddr, ext: &str) -> PathBuf {
+        let mut hasher = Sha256::new();
+        hasher.update(uri.trim_end_matches('/').as_bytes());
+        hasher.update(b"\n");
+        hasher.update(format!("{:#x}", module).as_bytes());
+        let key = hex::encode(hasher.finalize());
+        self.dir.join(&key[..2]).join(format!("{}.{}", key, ext))
+    }
+
+    pub fn load(&self, uri: &str, module: &ModAddr) -> Result<(PathBuf, Vec<u8>)> {
+        let path = self.entry_path(uri, module, BYTECODE_EXT);
+        let bytes = std::fs::read(&path)
+            .map_err(|err| format_err!("{:#x} is not cached: {}", module, err))?;
+        if let Some(meta) = self.meta(uri, module) {
+            trace!(
+                "{:#x} is cached at {} since {}",
+                module,
+                path_to_string(&path),
+                meta.fetched
+            );
+        }
+        Ok((path, bytes))
+    }
+
+    pub fn meta(&self, uri: &str, module: &ModAddr) -> Option<CacheMeta> {
+        let path = self.entry_path(uri, module, META_EXT);
+        let src = std::fs::read_to_string(path).ok()?;
+        serde_json::from_str(&src).ok()
+    }
+
+    /// Saves fetched bytecode replacing previous entry.
+    /// Files are renamed into place, so a concurrent or interrupted run never loads a partial entry.
+    pub fn store(&self, uri: &str, module: &ModAddr, bytes: &[u8]) -> Result<()> {
+        let path = self.entry_path(uri, module, BYTECODE_EXT);
+        let meta = CacheMeta {
+            uri: uri.to_owned(),
+            module: format!("{:#x}", module),
+            fetched: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map(|d| d.as_secs())
+                .unwrap_or_default(),
+        };
+        write_atomic(&path, bytes)?;
+        write_atomic(
+            &self.entry_path(uri, module, META_EXT),
+            serde_json::to_string_pretty(&meta)?.as_bytes(),
+        )?;
+        trace!("{:#x} cached at {}", module, path_to_string(&path));
+        Ok(())
+    }
+
+    /// Removes all cached entries.
+    pub fn purge(&self) -> Result<()> {
+        if self.dir.exists() {
+            std::fs::remove_dir_all(&self.dir).map_err(|err| {
+                format_err!(
+                    "Unable to purge cache {}: {}",
+                    path_to_string(&self.dir),
+                    err
+                )
+            })?;
+        }
+        info!("cache {} purged", path_to_string(&self.dir));
+        Ok(())
+    }
+}
+
+/// Searches modules fetched from the data-source `uri` before.
+pub struct CacheDependencySearch {
+    cache: Arc<DependencyCache>,
+    uri: String,
+}
+
+impl CacheDependencySearch {
+    pub fn new(cache: Arc<DependencyCache>, uri: String) -> Self {
+        Self { cache, uri }
+    }
+}
+
+impl<Q> DependencySearch<Q> for CacheDependencySearch
+where
+    Q: Into<ModAddr>,
+{
+    fn search(&self, module: Q) -> anyhow::Result<(DependencySource, Vec<u8>)> {
+        let m: ModAddr = module.into();
+        self.cache
+            .load(&self.uri, &m)
+            .map(|(path, bytes)| (DependencySource::Cache(path), bytes))
+    }
+}
+
+impl<Q> Into<Box<dyn DependencySearch<Q>>> for CacheDependencySearch
+where
+    Q: Into<ModAddr>,
+{
+    fn into(self) -> Box<dyn DependencySearch<Q>> {
+        Box::new(self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use libra::libra_types::account_address::AccountAddress;
+
+    const URI: &str = "http://localhost:8080/";
+
+    fn temp_cache(name: &str) -> DependencyCache {
+        let dir = std::env::temp_dir().join(format!(
+            "{}-test-{}-{}",
+            CACHE_DIR_NAME,
+            name,
+            std::process::id()
+        ));
+        std::fs::remove_dir_all(&dir).ok();
+        DependencyCache::new(dir)
+    }
+
+    fn module(name: &str) -> ModAddr {
+        ModAddr::new(AccountAddress::new([1; AccountAddress::LENGTH]), name)
+    }
+
+    #[test]
+    fn store_and_load() {
+        let cache = temp_cache("store");
+        let m = module("Coins");
+        assert!(cache.load(URI, &m).is_err());
+
+        cache.store(URI, &m, &[1, 2, 3]).unwrap();
+        let (path, bytes) = cache.load(URI, &m).unwrap();
+        assert!(path.starts_with(cache.dir()));
+        assert_eq!(bytes, vec![1, 2, 3]);
+        let meta = cache.meta(URI, &m).unwrap();
+        assert_eq!(meta.uri, URI);
+        assert_eq!(meta.module, format!("{:#x}", m));
+
+        // the trailing slash doesn't change the entry, another node does:
+        assert!(cache.load(URI.trim_end_matches('/'), &m).is_ok());
+        assert!(cache.load("http://localhost:8081/", &m).is_err());
+        assert!(cache.load(URI, &module("Dfi")).is_err());
+
+        // entry is replaced, no temporary files are left:
+        cache.store(URI, &m, &[4]).unwrap();
+        assert_eq!(cache.load(URI, &m).unwrap().1, vec![4]);
+        let entry_dir = path.parent().unwrap();
+        let names: Vec<_> = std::fs::read_dir(entry_dir)
+            .unwrap()
+            .map(|e| e.unwrap().file_name().to_string_lossy().into_owned())
+            .collect();
+        assert_eq!(names.len(), 2, "{:?}", names);
+        assert!(names.iter().all(|name| !name.ends_with(".tmp")));
+
+        cache.purge().unwrap();
+    }
+
+    #[test]
+    fn purge() {
+        let cache = temp_cache("purge");
+        // missing directory is purged as well:
+        cache.purge().unwrap();
+
+        let m = module("Coins");
+        cache.store(URI, &m, &[1, 2, 3]).unwrap();
+        cache.purge().unwrap();
+        assert!(!cache.dir().exists());
+        assert!(cache.load(URI, &m).is_err());
+        assert!(cache.meta(URI, &m).is_none());
+    }
+}
@@ -3,6 +3,8 @@ use std::{hash::Hash, collections::HashMap, borrow::Borrow};
 use libra::{vm::CompiledModule, libra_types::account_address::AccountAddress};
 use libra::{move_core_types::identifier::IdentStr, vm::access::ModuleAccess};
 use crate::disasm;
+use crate::disasm::DEFAULT_SCRIPT_ADDRESS;
+use crate::error::InputError;
 use crate::output::utils::path_to_string;
 use crate::types::{IntoModAddr, ModAddr};
 use crate::extract::prelude::*;
@@ -30,9 +32,18 @@ pub type ModMap = HashMap<ModAddr, ModInfo>;
 pub trait DependencyMap {
     fn insert_file(&mut self, file_path: PathBuf, bytes: Vec<u8>);
     fn insert_mod_bin<Src>(&mut self, source: Src, bytes: Vec<u8>)
+    where
+        Src: Into<DependencySource>;
+    /// Inserts module found by the `requested` address,
+    /// the placeholder keeps the address if the bytecode is broken.
+    fn insert_requested_bin<Src>(&mut self, requested: ModAddr, source: Src, bytes: Vec<u8>)
     where
         Src: Into<DependencySource>;
     fn insert_mod<Src>(&mut self, source: Src, bytecode: CompiledModule)
+    where
+        Src: Into<DependencySource>;
+    /// Inserts placeholder for the module which can't be loaded.
+    fn insert_broken<Src>(&mut self, source: Src, requested: Option<ModAddr>, error: InputError)
     where
         Src: Into<DependencySource>;
     fn build_deps_links(&mut self);
@@ -46,10 +57,7 @@ pub trait DependencyMap {
 impl DependencyMap for ModMap {
     fn insert_file(&mut self, file_path: PathBuf, bytes: Vec<u8>) {
         debug!("inserting file {}", path_to_string(&file_path));
-        let m = disasm::deserialize_module(&bytes)
-            // TODO: catch error and then add error plug
-            .expect("Module can't be deserialized");
-        self.insert_mod(file_path, m);
+        self.insert_mod_bin(file_path, bytes);
     }
 
     fn insert_mod_bin<Src>(&mut self, source: Src, bytes: Vec<u8>)
@@ -58,19 +66,37 @@ impl DependencyMap for ModMap {
     {
         let source = source.into();
         debug!("inserting {} bytes by {:?}", bytes.len(), source);
-        let m = disasm::deserialize_module(&bytes)
-            // TODO: catch error and then add error plug
-            .expect("Module can't be deserialized");
-        self.insert_mod(source, m);
+        match disasm::deserialize_module(&bytes) {
+            Ok(m) => self.insert_mod(source, m),
+            Err(err) => self.insert_broken(source, None, err.into()),
+        }
+    }
+
+    fn insert_requested_bin<Src>(&mut self, requested: ModAddr, source: Src, bytes: Vec<u8>)
+    where
+        Src: Into<DependencySource>,
+    {
+        let source = source.into();
+        debug!(
+            "inserting {} bytes of {:#x} by {:?}",
+            bytes.len(),
+            requested,
+            source
+        );
+        match disasm::deserialize_module(&bytes) {
+            Ok(m) => self.insert_mod(source, m),
+            Err(err) => self.insert_broken(source, Some(requested), err.into()),
+        }
     }
 
     fn insert_mod<Src>(&mut self, source: Src, bytecode: CompiledModule)
     where
         Src: Into<DependencySource>,
     {
-        let addr = (bytecode.address(), bytecode.name()).into();
+        let addr: ModAddr = (bytecode.address(), bytecode.name()).into();
         let mut info = ModInfo {
-            bytecode,
+            addr: addr.clone(),
+            state: ModState::Resolved(bytecode),
             source: source.into(),
             dependencies: Default::default(),
         };
@@ -89,10 +115,40 @@ impl DependencyMap for ModMap {
         self.insert(addr, info);
     }
 
+    fn insert_broken<Src>(&mut self, source: Src, requested: Option<ModAddr>, error: InputError)
+    where
+        Src: Into<DependencySource>,
+    {
+        let source = source.into();
+        warn!("broken dependency {}: {}", source, error);
+
+        // real address of files is unknown, so the placeholder is named by the source:
+        let addr = match (&requested, &source) {
+            (Some(addr), _) => addr.to_owned(),
+            (None, DependencySource::Fs(path)) => ModAddr::new(
+                AccountAddress::new(DEFAULT_SCRIPT_ADDRESS),
+                path_to_string(path),
+            ),
+            (None, _) => ModAddr::new(
+                AccountAddress::new(DEFAULT_SCRIPT_ADDRESS),
+                format!("#{}", self.values().filter(|info| info.is_broken()).count()),
+            ),
+        };
+        let info = ModInfo {
+            addr: addr.clone(),
+            state: ModState::Broken { error, requested },
+            source,
+            dependencies: Default::default(),
+        };
+        self.insert(addr, info);
+    }
+
     fn build_deps_for(info: &mut ModInfo) {
         if info.dependencies.is_empty() {
-            let deps = extract_module_mod_handles(info.bytecode());
-            info.dependencies.extend(deps);
+            if let Some(bytecode) = info.bytecode() {
+                let deps = extract_module_mod_handles(bytecode);
+                info.dependencies.extend(deps);
+            }
         }
     }
 
@@ -110,25 +166,55 @@ impl DependencyMap for ModMap {
 
 pub struct ModInfo {
     source: DependencySource,
-    bytecode: CompiledModule,
+    addr: ModAddr,
+    state: ModState,
     dependencies: Vec<ModAddr>,
 }
 
+pub enum ModState {
+    Resolved(CompiledModule),
+    /// Module can't be loaded, so the placeholder keeps the reason
+    /// and the address if the module was requested by it.
+    Broken {
+        error: InputError,
+        requested: Option<ModAddr>,
+    },
+}
+
 impl ModInfo {
     pub fn source(&self) -> &DependencySource {
         &self.source
     }
-    pub fn name(&self) -> &IdentStr {
-        self.bytecode.name()
+    pub fn name(&self) -> &str {
+        self.addr.name()
     }
     pub fn address(&self) -> &AccountAddress {
-        self.bytecode.address()
+        self.addr.addr()
     }
     pub fn mod_addr(&self) -> ModAddr {
-        (self.address(), self.name()).into_mod_addr()
+        self.addr.clone()
     }
-    pub fn bytecode(&self) -> &CompiledModule {
-        &self.bytecode
+    pub fn bytecode(&self) -> Option<&CompiledModule> {
+        match &self.state {
+            ModState::Resolved(bytecode) => Some(bytecode),
+            ModState::Broken { .. } => None,
+        }
+    }
+    pub fn error(&self) -> Option<&InputError> {
+        match &self.state {
+            ModState::Resolved(_) => None,
+            ModState::Broken { error, .. } => Some(error),
+        }
+    }
+    /// Address the broken module was requested by.
+    pub fn requested(&self) -> Option<&ModAddr> {
+        match &self.state {
+            ModState::Resolved(_) => None,
+            ModState::Broken { requested, .. } => requested.as_ref(),
+        }
+    }
+    pub fn is_broken(&self) -> bool {
+        self.error().is_some()
     }
     pub fn dependencies(&self) -> &[ModAddr] {
         &self.dependencies[..]
@@ -137,3 +223,29 @@ impl ModInfo {
         &mut self.dependencies[..]
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn broken_files_by_path() {
+        let mut map = ModMap::default();
+        map.insert_file(PathBuf::from("a/Coin.mv"), vec![0; 4]);
+        map.insert_file(PathBuf::from("b/Coin.mv"), vec![0; 4]);
+        assert_eq!(2, map.len());
+        assert!(map
+            .values()
+            .all(|info| info.is_broken() && info.requested().is_none()));
+    }
+
+    #[test]
+    fn broken_requested_by_address() {
+        let mut map = ModMap::default();
+        let addr = ModAddr::test_addr_42();
+        map.insert_requested_bin(addr.clone(), DependencySource::Net, vec![0; 4]);
+        let info = &map[&addr];
+        assert!(info.is_broken());
+        assert_eq!(Some(&addr), info.requested());
+    }
+}
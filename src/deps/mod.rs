@@ -1,12 +1,15 @@
 use libra::libra_types::account_address::AccountAddress;
 use std::path::PathBuf;
+use crate::output::utils::path_to_string;
 
+pub mod cache;
 pub mod map;
 pub mod offline;
 pub mod online;
 pub mod resolver;
 
-pub trait DependencySearch<Q> {
+/// Searchers are shared between workers of the resolver.
+pub trait DependencySearch<Q>: Send + Sync {
     fn search(&self, query: Q) -> anyhow::Result<(DependencySource, Vec<u8>)>;
 }
 
@@ -28,6 +31,8 @@ impl<Q> DependencySearch<Q> for Box<dyn DependencySearch<Q>> {
 pub enum DependencySource {
     Fs(PathBuf),
     Net,
+    /// Previously fetched from the network.
+    Cache(PathBuf),
     None,
 }
 
@@ -36,3 +41,14 @@ impl<T: Into<PathBuf>> From<T> for DependencySource {
         DependencySource::Fs(p.into())
     }
 }
+
+impl std::fmt::Display for DependencySource {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            DependencySource::Fs(path) => write!(f, "{}", path_to_string(path)),
+            DependencySource::Net => write!(f, "network"),
+            DependencySource::Cache(path) => write!(f, "cache {}", path_to_string(path)),
+            DependencySource::None => write!(f, "unknown"),
+        }
+    }
+}
@@ -1,24 +1,38 @@
 use net::NetCfg;
 use libra::libra_types::account_address::AccountAddress;
 use crate::cli::InputNet;
+use std::sync::Arc;
+use super::cache::DependencyCache;
 use super::DependencySource;
 use super::DependencySearch;
 use crate::types::ModAddr;
+use crate::disasm;
 
 pub struct OnlineDependencySearch<S: AsRef<str>> {
     config: NetCfg<S>,
+    /// Stores fetched modules if set.
+    cache: Option<Arc<DependencyCache>>,
 }
 
 impl<Q, S> DependencySearch<Q> for OnlineDependencySearch<S>
 where
     Q: Into<ModAddr>,
-    S: AsRef<str>,
+    S: AsRef<str> + Send + Sync,
 {
     fn search(&self, module: Q) -> anyhow::Result<(DependencySource, Vec<u8>)> {
         let m: ModAddr = module.into();
         trace!("net request for {:#x}", m);
         let (addr, name) = m.split();
-        net::get(&addr, name, &self.config).map(|bytes| (DependencySource::Net, bytes))
+        let bytes = net::get(&addr, name, &self.config)?;
+        if let Some(cache) = &self.cache {
+            // broken bytecode is reported by the resolver, but it's never cached:
+            if let Err(err) = disasm::deserialize_module(&bytes) {
+                warn!("{:#x} is not cached: {:?}", m, err);
+            } else if let Err(err) = cache.store(self.config.node_base_url(), &m, &bytes) {
+                warn!("unable to cache {:#x}: {}", m, err);
+            }
+        }
+        Ok((DependencySource::Net, bytes))
     }
 }
 
@@ -43,6 +57,14 @@ where
 impl<S: AsRef<str>> OnlineDependencySearch<S> {
     pub fn new(uri: S) -> Self {
         let config = NetCfg::new(uri);
-        Self { config }
+        Self {
+            config,
+            cache: None,
+        }
+    }
+
+    pub fn with_cache(mut self, cache: Arc<DependencyCache>) -> Self {
+        self.cache = Some(cache);
+        self
     }
 }
@@ -1,4 +1,6 @@
 use std::{collections::HashMap, cell::RefCell, hash::Hash};
+use rayon::prelude::*;
+use rayon::ThreadPoolBuilder;
 use libra::libra_types::account_address::AccountAddress;
 use super::map::{ModMap, AsMap, ModInfo, DependencyMap};
 use super::DependencySource;
@@ -7,10 +9,15 @@ use crate::types::ModAddr;
 
 pub type UnresolvedMap<K> = HashMap<K, anyhow::Error>;
 
+/// Default number of concurrent searches.
+pub const DEFAULT_JOBS: usize = 8;
+
 pub struct DependencyResolverMap<Q, Storage> {
     map: Storage,
     unresolved: UnresolvedMap<Q>,
     searchers: Vec<Box<dyn DependencySearch<Q>>>,
+    /// Max number of concurrent searches.
+    jobs: usize,
 }
 
 impl<Q, Storage> DependencyResolverMap<Q, Storage> {
@@ -19,9 +26,15 @@ impl<Q, Storage> DependencyResolverMap<Q, Storage> {
             map,
             searchers: Default::default(),
             unresolved: Default::default(),
+            jobs: DEFAULT_JOBS,
         }
     }
 
+    pub fn with_jobs(mut self, jobs: usize) -> Self {
+        self.jobs = jobs.max(1);
+        self
+    }
+
     pub fn split(self) -> (Storage, UnresolvedMap<Q>) {
         (self.map, self.unresolved)
     }
@@ -34,52 +47,91 @@ impl<Q, Storage> DependencyResolverMap<Q, Storage> {
     }
 }
 
+/// Asks searchers in order, returns the first found or the last error.
+fn search_with<Q: Clone>(
+    searchers: &[Box<dyn DependencySearch<Q>>],
+    query: &Q,
+) -> anyhow::Result<(DependencySource, Vec<u8>)> {
+    let mut error = None;
+    for s in searchers {
+        match s.search(query.to_owned()) {
+            Ok(found) => return Ok(found),
+            Err(err) => error = Some(err),
+        }
+    }
+    Err(error.unwrap_or_else(|| anyhow::anyhow!("no data-sources to search")))
+}
+
 impl<Q, Storage> DependencyResolverMap<Q, Storage>
 where
     Q: ToOwned<Owned = Q>,
     Q: Clone + Eq + PartialEq + Hash,
 {
     pub fn search(&mut self, query: &Q) -> Option<(DependencySource, Vec<u8>)> {
-        let mut error = None;
-        let result = self
-            .searchers
-            .iter()
-            .filter_map(|s| {
-                s.search(query.to_owned())
-                    .map_err(|err| {
-                        error = Some(err);
-                    })
-                    .ok()
-            })
-            .next();
-
-        if let (None, Some(error)) = (&result, error) {
-            self.unresolved.insert(query.to_owned(), error);
+        match search_with(&self.searchers, query) {
+            Ok(found) => Some(found),
+            Err(err) => {
+                if !self.searchers.is_empty() {
+                    self.unresolved.insert(query.to_owned(), err);
+                }
+                None
+            }
         }
-
-        result
     }
 }
 
 impl DependencyResolverMap<ModAddr, ModMap> {
+    /// Searches `keys` which are not resolved yet concurrently and adds found ones to the map.
+    /// Results are added in order of the keys, so the resulting map doesn't depend
+    /// on the order of responses.
     pub fn prefetch_deps(&mut self, keys: &[ModAddr]) -> Vec<ModAddr> {
-        let keys: Vec<_> = keys
+        let mut keys: Vec<_> = keys
             .iter()
-            .filter(|key| !self.map.as_map().contains_key(key))
+            .filter(|key| {
+                !self.map.as_map().contains_key(key) && !self.unresolved.contains_key(key)
+            })
+            .cloned()
             .collect();
+        // every module is requested once:
+        keys.sort_by_key(|key| format!("{:#x}", key));
+        keys.dedup();
+        if keys.is_empty() {
+            return Vec::new();
+        }
+
+        let searchers = &self.searchers;
+        let results: Vec<_> = match ThreadPoolBuilder::new().num_threads(self.jobs).build() {
+            Ok(pool) => pool.install(|| {
+                keys.par_iter()
+                    .map(|key| search_with(searchers, key))
+                    .collect()
+            }),
+            Err(err) => {
+                warn!("unable to start resolver workers: {}", err);
+                keys.iter().map(|key| search_with(searchers, key)).collect()
+            }
+        };
 
         let mut added = Vec::new();
-        for key in keys {
-            if let Some(found) = self.search(&key) {
-                self.map.insert_mod_bin(found.0, found.1);
-                added.push(key.to_owned());
+        for (key, result) in keys.into_iter().zip(results) {
+            match result {
+                Ok((source, bytes)) => {
+                    self.map.insert_requested_bin(key.to_owned(), source, bytes);
+                    added.push(key);
+                }
+                Err(err) if !self.searchers.is_empty() => {
+                    self.unresolved.insert(key, err);
+                }
+                Err(_) => {}
             }
         }
         added
     }
 
+    /// Resolves dependencies of the map level by level,
+    /// modules of the same level are searched concurrently.
     pub fn prefetch_deps_recursively(&mut self) {
-        let mut keys: Vec<_> = self
+        let mut frontier: Vec<_> = self
             .map
             .as_map()
             .iter()
@@ -87,14 +139,121 @@ impl DependencyResolverMap<ModAddr, ModMap> {
             .cloned()
             .collect();
 
-        while let Some(key) = keys.pop() {
-            let added = self.prefetch_deps(&[key]);
-            let deps_of_added = added
+        while !frontier.is_empty() {
+            trace!("prefetching level of {} deps", frontier.len());
+            let added = self.prefetch_deps(&frontier);
+            frontier = added
                 .iter()
                 .filter_map(|key| self.map.as_map().get(key).map(|info| info.dependencies()))
                 .flatten()
-                .cloned();
-            keys.extend(deps_of_added);
+                .cloned()
+                .collect();
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{Arc, Mutex};
+    use std::time::Duration;
+    use libra::vm::access::ModuleAccess;
+    use crate::fixtures;
+    use crate::types::IntoModAddr;
+    use super::*;
+
+    /// Serves the libra_std modules and records the queries.
+    /// Responses of later modules come first if `reverse` is set.
+    struct StubSearch {
+        modules: Vec<(ModAddr, Vec<u8>)>,
+        queries: Arc<Mutex<Vec<ModAddr>>>,
+        reverse: bool,
+    }
+
+    impl DependencySearch<ModAddr> for StubSearch {
+        fn search(&self, query: ModAddr) -> anyhow::Result<(DependencySource, Vec<u8>)> {
+            self.queries.lock().unwrap().push(query.to_owned());
+            let pos = self.modules.iter().position(|(addr, _)| addr == &query);
+            let delay = match pos {
+                Some(pos) if self.reverse => self.modules.len() - pos,
+                Some(pos) => pos,
+                None => 0,
+            };
+            std::thread::sleep(Duration::from_millis(delay as u64));
+            match pos {
+                Some(pos) => Ok((DependencySource::Net, self.modules[pos].1.to_owned())),
+                None => Err(anyhow::anyhow!("{:#x} not found", query)),
+            }
+        }
+    }
+
+    fn std_modules() -> Vec<(ModAddr, Vec<u8>)> {
+        fixtures::names("modules")
+            .into_iter()
+            .map(|name| {
+                let bytes = fixtures::adapted(&format!("modules/{}", name));
+                let module = crate::disasm::deserialize_module(&bytes).unwrap();
+                (module.self_id().into_mod_addr(), bytes)
+            })
+            .collect()
+    }
+
+    fn resolve(reverse: bool) -> (Vec<ModAddr>, Vec<ModAddr>, Vec<ModAddr>, Vec<ModAddr>) {
+        let modules = std_modules();
+        let account = modules
+            .iter()
+            .find(|(addr, _)| addr.name() == "LibraAccount")
+            .map(|(addr, _)| addr.to_owned())
+            .unwrap();
+        let missing = ModAddr::new(account.addr().to_owned(), "Missing");
+
+        let queries = Arc::new(Mutex::new(Vec::new()));
+        let mut resolver = DependencyResolverMap::new(ModMap::default()).with_jobs(4);
+        resolver.add_searcher(Box::new(StubSearch {
+            modules,
+            queries: queries.clone(),
+            reverse,
+        }) as Box<dyn DependencySearch<ModAddr>>);
+
+        let added =
+            resolver.prefetch_deps(&[account.to_owned(), missing.to_owned(), account.to_owned()]);
+        assert_eq!(added, vec![account]);
+        resolver.prefetch_deps_recursively();
+        // resolved and unresolved keys are not requested again:
+        assert!(resolver.prefetch_deps(&[missing]).is_empty());
+
+        let (map, unresolved) = resolver.split();
+        let sorted = |keys: Vec<ModAddr>| {
+            let mut keys = keys;
+            keys.sort_by_key(|key| format!("{:#x}", key));
+            keys
+        };
+        let queries = queries.lock().unwrap().to_owned();
+        (
+            added,
+            sorted(map.keys().cloned().collect()),
+            sorted(unresolved.keys().cloned().collect()),
+            queries,
+        )
+    }
+
+    #[test]
+    fn prefetch_deps() {
+        let (added, resolved, unresolved, queries) = resolve(false);
+
+        let mut unique = queries.to_owned();
+        unique.sort_by_key(|key| format!("{:#x}", key));
+        unique.dedup();
+        assert_eq!(queries.len(), unique.len(), "{:?}", queries);
+        assert_eq!(unique.len(), resolved.len() + unresolved.len());
+
+        assert!(resolved.len() > 1);
+        assert_eq!(unresolved.len(), 1);
+        assert_eq!(unresolved[0].name(), "Missing");
+
+        // the result doesn't depend on the order of responses:
+        let (r_added, r_resolved, r_unresolved, _) = resolve(true);
+        assert_eq!(added, r_added);
+        assert_eq!(resolved, r_resolved);
+        assert_eq!(unresolved, r_unresolved);
+    }
+}
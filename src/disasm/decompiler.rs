@@ -0,0 +1,1479 @@
+//! Reconstructs readable Move source from the stack-based bytecode.
+//!
+//! Function bodies are executed symbolically over the control-flow graph:
+//! stack values become expressions, stores and side effects become statements,
+//! and branches are structured back into `if`/`else`, `while` and `loop`.
+//! The result is meant to be read by human, it doesn't have to compile.
+
+use std::collections::{BTreeMap, HashMap};
+use anyhow::{bail, format_err, Result};
+use libra::bytecode_verifier::control_flow_graph::{ControlFlowGraph, VMControlFlowGraph};
+use libra::vm::access::{ModuleAccess, ScriptAccess};
+use libra::vm::file_format::{
+    Bytecode, CodeOffset, CodeUnit, CompiledModule, Constant, FieldHandleIndex, Kind,
+    ModuleHandleIndex, SignatureIndex, SignatureToken, StructDefinitionIndex,
+    StructDefInstantiationIndex, StructFieldInformation, StructHandleIndex,
+};
+use crate::cli::Dialect;
+use crate::types::{fmt_address, ConstValue};
+use super::{CompiledMoveRef, MoveAccess};
+
+const INDENT: &str = "    ";
+
+/// Decompiles the module or script into Move source.
+pub fn decompile(bc: CompiledMoveRef, dialect: Dialect) -> Result<String> {
+    Decompiler::new(bc).with_dialect(dialect).decompile()
+}
+
+pub struct Decompiler<'a> {
+    bc: CompiledMoveRef<'a>,
+    /// Dialect of address literals.
+    dialect: Dialect,
+}
+
+impl<'a> Decompiler<'a> {
+    pub fn new(bc: CompiledMoveRef<'a>) -> Self {
+        // adapted bytecode has addresses of the dfinance length:
+        Self {
+            bc,
+            dialect: Dialect::Dfinance,
+        }
+    }
+
+    pub fn with_dialect(mut self, dialect: Dialect) -> Self {
+        self.dialect = dialect;
+        self
+    }
+
+    pub fn decompile(&self) -> Result<String> {
+        match self.bc {
+            CompiledMoveRef::Module(m) => self.decompile_module(m),
+            CompiledMoveRef::Script(_) => self.decompile_script(),
+        }
+    }
+
+    fn decompile_module(&self, m: &CompiledModule) -> Result<String> {
+        let mut items = vec![self.uses()];
+        for def in m.struct_defs() {
+            items.push(self.struct_def(m, def.struct_handle, &def.field_information));
+        }
+        for def in m.function_defs() {
+            let handle = m.function_handle_at(def.function);
+            let visibility = if def.is_native() {
+                "native "
+            } else if def.is_public() {
+                "public "
+            } else {
+                ""
+            };
+            let acquires: Vec<_> = def
+                .acquires_global_resources
+                .iter()
+                .map(|idx| self.struct_name(m.struct_def_at(*idx).struct_handle))
+                .collect();
+            let returns = &m.signature_at(handle.return_).0;
+            let mut header = format!(
+                "{}fun {}{}",
+                visibility,
+                m.identifier_at(handle.name),
+                self.signature(&handle.type_parameters, handle.parameters, returns)
+            );
+            if !acquires.is_empty() {
+                header.push_str(&format!(" acquires {}", acquires.join(", ")));
+            }
+            items.push(match &def.code {
+                Some(code) => self.function(header, handle.parameters, returns.len(), code),
+                None => format!("{};", header),
+            });
+        }
+
+        Ok(format!(
+            "address {} {{\nmodule {} {{\n{}\n}}\n}}\n",
+            fmt_address(&self.bc.address(), self.dialect),
+            self.bc.name(),
+            indent(&join_items(items))
+        ))
+    }
+
+    fn decompile_script(&self) -> Result<String> {
+        let script = match self.bc {
+            CompiledMoveRef::Script(s) => s.as_inner(),
+            _ => unreachable!(),
+        };
+        let header = format!(
+            "fun main{}",
+            self.signature(&script.type_parameters, script.parameters, &[])
+        );
+        let main = self.function(header, script.parameters, 0, &script.code);
+        Ok(format!(
+            "script {{\n{}\n}}\n",
+            indent(&join_items(vec![self.uses(), main]))
+        ))
+    }
+
+    /// `use` declarations for all imported modules.
+    fn uses(&self) -> String {
+        let mut uses: Vec<_> = self
+            .bc
+            .module_handles()
+            .iter()
+            .enumerate()
+            .filter(|(i, _)| !self.is_self_module(ModuleHandleIndex::new(*i as u16)))
+            .map(|(_, mh)| {
+                format!(
+                    "use {}::{};",
+                    fmt_address(self.bc.address_identifier_at(mh.address), self.dialect),
+                    self.bc.identifier_at(mh.name)
+                )
+            })
+            .collect();
+        uses.sort();
+        uses.join("\n")
+    }
+
+    fn struct_def(
+        &self,
+        m: &CompiledModule,
+        handle: StructHandleIndex,
+        fields: &StructFieldInformation,
+    ) -> String {
+        let sh = m.struct_handle_at(handle);
+        let kind = if sh.is_nominal_resource {
+            "resource struct"
+        } else {
+            "struct"
+        };
+        let name = format!(
+            "{}{}",
+            m.identifier_at(sh.name),
+            fmt_type_params(&sh.type_parameters)
+        );
+        match fields {
+            StructFieldInformation::Native => format!("native {} {};", kind, name),
+            StructFieldInformation::Declared(fields) => {
+                let fields: Vec<_> = fields
+                    .iter()
+                    .map(|f| format!("{}: {},", m.identifier_at(f.name), self.ty(&f.signature.0)))
+                    .collect();
+                format!("{} {} {{\n{}\n}}", kind, name, indent(&fields.join("\n")))
+            }
+        }
+    }
+
+    /// Type parameters, parameters and return types of the function.
+    fn signature(
+        &self,
+        type_params: &[Kind],
+        params: SignatureIndex,
+        returns: &[SignatureToken],
+    ) -> String {
+        let params: Vec<_> = self
+            .bc
+            .signature_at(params)
+            .0
+            .iter()
+            .enumerate()
+            .map(|(i, ty)| format!("{}: {}", local_name(i, usize::MAX), self.ty(ty)))
+            .collect();
+        let returns: Vec<_> = returns.iter().map(|ty| self.ty(ty)).collect();
+        let returns = match returns.len() {
+            0 => String::new(),
+            1 => format!(": {}", returns[0]),
+            _ => format!(": ({})", returns.join(", ")),
+        };
+        format!(
+            "{}({}){}",
+            fmt_type_params(type_params),
+            params.join(", "),
+            returns
+        )
+    }
+
+    fn function(
+        &self,
+        header: String,
+        params: SignatureIndex,
+        returns_len: usize,
+        code: &CodeUnit,
+    ) -> String {
+        let params_len = self.bc.signature_at(params).0.len();
+
+        // locals are indexed after parameters:
+        let mut lines: Vec<_> = self
+            .bc
+            .signature_at(code.locals)
+            .0
+            .iter()
+            .enumerate()
+            .map(|(i, ty)| {
+                let name = local_name(params_len + i, params_len);
+                format!("let {}: {};", name, self.ty(ty))
+            })
+            .collect();
+
+        let body = FnDecompiler::new(self, code, params_len, returns_len).decompile();
+        match body {
+            Ok(stmts) => lines.extend(stmts.iter().map(|s| s.to_string())),
+            Err(err) => {
+                warn!("unable to decompile body of `{}`: {}", header, err);
+                lines.push(format!("/* unable to decompile: {} */", err));
+            }
+        }
+
+        if lines.is_empty() {
+            format!("{} {{}}", header)
+        } else {
+            format!("{} {{\n{}\n}}", header, indent(&lines.join("\n")))
+        }
+    }
+
+    /// Abort sites of the function body with conditions of the branches leading to them.
+    pub fn aborts(
+        &self,
+        params: SignatureIndex,
+        returns_len: usize,
+        code: &CodeUnit,
+    ) -> Result<Vec<AbortSite>> {
+        let params_len = self.bc.signature_at(params).0.len();
+        let mut f = FnDecompiler::new(self, code, params_len, returns_len);
+        f.run()?;
+        Ok(f.aborts)
+    }
+
+    fn module(&self) -> Result<&'a CompiledModule> {
+        match self.bc {
+            CompiledMoveRef::Module(m) => Ok(m),
+            CompiledMoveRef::Script(_) => bail!("script can't access struct definitions"),
+        }
+    }
+
+    fn is_self_module(&self, idx: ModuleHandleIndex) -> bool {
+        match self.bc {
+            CompiledMoveRef::Module(m) => m.self_handle_idx() == idx,
+            CompiledMoveRef::Script(_) => false,
+        }
+    }
+
+    /// Name of the struct, qualified with module if it's imported.
+    fn struct_name(&self, idx: StructHandleIndex) -> String {
+        let sh = self.bc.struct_handle_at(idx);
+        self.qualified(sh.module, self.bc.identifier_at(sh.name).as_str())
+    }
+
+    fn qualified(&self, module: ModuleHandleIndex, name: &str) -> String {
+        if self.is_self_module(module) {
+            name.to_owned()
+        } else {
+            let mh = self.bc.module_handle_at(module);
+            format!("{}::{}", self.bc.identifier_at(mh.name), name)
+        }
+    }
+
+    fn ty(&self, ty: &SignatureToken) -> String {
+        match ty {
+            SignatureToken::Bool => "bool".to_owned(),
+            SignatureToken::U8 => "u8".to_owned(),
+            SignatureToken::U64 => "u64".to_owned(),
+            SignatureToken::U128 => "u128".to_owned(),
+            SignatureToken::Address => "address".to_owned(),
+            SignatureToken::Signer => "signer".to_owned(),
+            SignatureToken::Vector(ty) => format!("vector<{}>", self.ty(ty)),
+            SignatureToken::Struct(idx) => self.struct_name(*idx),
+            SignatureToken::StructInstantiation(idx, tys) => {
+                format!("{}{}", self.struct_name(*idx), self.ty_args(tys))
+            }
+            SignatureToken::Reference(ty) => format!("&{}", self.ty(ty)),
+            SignatureToken::MutableReference(ty) => format!("&mut {}", self.ty(ty)),
+            SignatureToken::TypeParameter(idx) => format!("T{}", idx),
+        }
+    }
+
+    fn ty_args(&self, tys: &[SignatureToken]) -> String {
+        if tys.is_empty() {
+            String::new()
+        } else {
+            let tys: Vec<_> = tys.iter().map(|ty| self.ty(ty)).collect();
+            format!("<{}>", tys.join(", "))
+        }
+    }
+
+    fn ty_args_at(&self, idx: SignatureIndex) -> String {
+        self.ty_args(&self.bc.signature_at(idx).0)
+    }
+
+    fn constant(&self, constant: &Constant) -> Option<String> {
+        ConstValue::decode(constant).map(|v| v.to_literal(self.dialect))
+    }
+}
+
+fn fmt_type_params(kinds: &[Kind]) -> String {
+    if kinds.is_empty() {
+        return String::new();
+    }
+    let params: Vec<_> = kinds
+        .iter()
+        .enumerate()
+        .map(|(i, kind)| match kind {
+            Kind::All => format!("T{}", i),
+            Kind::Resource => format!("T{}: resource", i),
+            Kind::Copyable => format!("T{}: copyable", i),
+        })
+        .collect();
+    format!("<{}>", params.join(", "))
+}
+
+fn local_name(idx: usize, params_len: usize) -> String {
+    if idx < params_len {
+        format!("arg{}", idx)
+    } else {
+        format!("l{}", idx)
+    }
+}
+
+fn indent(s: &str) -> String {
+    s.lines()
+        .map(|line| {
+            if line.is_empty() {
+                String::new()
+            } else {
+                format!("{}{}", INDENT, line)
+            }
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+fn join_items(items: Vec<String>) -> String {
+    items
+        .into_iter()
+        .filter(|item| !item.is_empty())
+        .collect::<Vec<_>>()
+        .join("\n\n")
+}
+
+// Precedence of Move operators, higher binds tighter.
+const PREC_IF: u8 = 0;
+const PREC_OR: u8 = 1;
+const PREC_AND: u8 = 2;
+const PREC_CMP: u8 = 3;
+const PREC_BIT_OR: u8 = 4;
+const PREC_XOR: u8 = 5;
+const PREC_BIT_AND: u8 = 6;
+const PREC_SHIFT: u8 = 7;
+const PREC_ADD: u8 = 8;
+const PREC_MUL: u8 = 9;
+const PREC_UNARY: u8 = 10;
+const PREC_ATOM: u8 = 11;
+
+/// Symbolic stack value.
+#[derive(Debug, Clone)]
+struct Expr {
+    text: String,
+    prec: u8,
+    /// Location this value references, e.g. `x.f` for `&mut x.f`.
+    place: Option<String>,
+    /// Prefix suitable for field access, e.g. `x` for `move x`.
+    path: Option<String>,
+    /// Value is computed by a call or a resource move, so it can't be evaluated
+    /// after statements emitted later.
+    effect: bool,
+}
+
+impl Expr {
+    fn new<S: Into<String>>(text: S, prec: u8) -> Self {
+        Self {
+            text: text.into(),
+            prec,
+            place: None,
+            path: None,
+            effect: false,
+        }
+    }
+
+    fn atom<S: Into<String>>(text: S) -> Self {
+        Self::new(text, PREC_ATOM)
+    }
+
+    /// Text of the expression wrapped into parentheses if it binds weaker than `prec`.
+    fn wrap(&self, prec: u8) -> String {
+        if self.prec < prec {
+            format!("({})", self.text)
+        } else {
+            self.text.to_owned()
+        }
+    }
+
+    fn not(&self) -> Expr {
+        let not = match self.text.strip_prefix('!') {
+            Some(inner) if self.prec == PREC_UNARY => {
+                let inner = inner.strip_prefix('(').and_then(|s| s.strip_suffix(')'));
+                match inner {
+                    Some(inner) => Expr::new(inner, PREC_IF),
+                    None => Expr::atom(&self.text[1..]),
+                }
+            }
+            _ => Expr::new(format!("!{}", self.wrap(PREC_UNARY)), PREC_UNARY),
+        };
+        Expr {
+            effect: self.effect,
+            ..not
+        }
+    }
+
+    fn field_base(&self) -> String {
+        self.place
+            .clone()
+            .or_else(|| self.path.clone())
+            .unwrap_or_else(|| self.wrap(PREC_ATOM))
+    }
+}
+
+#[derive(Debug, Clone)]
+enum Stmt {
+    Line(String),
+    /// `return`, `abort`, `break` or `continue`.
+    Exit(String),
+    If(String, Vec<Stmt>, Vec<Stmt>),
+    While(String, Vec<Stmt>),
+    Loop(Vec<Stmt>),
+}
+
+impl Stmt {
+    fn fmt_block(stmts: &[Stmt]) -> String {
+        let lines: Vec<_> = stmts.iter().map(|s| s.to_string()).collect();
+        if lines.is_empty() {
+            "{}".to_owned()
+        } else {
+            format!("{{\n{}\n}}", indent(&lines.join("\n")))
+        }
+    }
+
+    fn is_exit(&self) -> bool {
+        matches!(self, Stmt::Exit(_))
+    }
+}
+
+impl std::fmt::Display for Stmt {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Stmt::Line(s) | Stmt::Exit(s) => write!(f, "{};", s),
+            Stmt::If(cond, then, els) if els.is_empty() => {
+                write!(f, "if ({}) {}", cond, Stmt::fmt_block(then))
+            }
+            Stmt::If(cond, then, els) => match &els[..] {
+                [nested @ Stmt::If(..)] => {
+                    write!(f, "if ({}) {} else {}", cond, Stmt::fmt_block(then), nested)
+                }
+                _ => write!(
+                    f,
+                    "if ({}) {} else {}",
+                    cond,
+                    Stmt::fmt_block(then),
+                    Stmt::fmt_block(els)
+                ),
+            },
+            Stmt::While(cond, body) => write!(f, "while ({}) {}", cond, Stmt::fmt_block(body)),
+            Stmt::Loop(body) => write!(f, "loop {}", Stmt::fmt_block(body)),
+        }
+    }
+}
+
+/// `Abort` instruction reached by the decompiler.
+#[derive(Debug, Clone)]
+pub struct AbortSite {
+    pub offset: CodeOffset,
+    /// Expression of the abort code.
+    pub operand: String,
+    /// Conditions of the enclosing branches, outermost first.
+    /// Each one is parenthesised if it binds weaker than `&&`.
+    pub conditions: Vec<String>,
+}
+
+impl AbortSite {
+    /// Conditions joined by `&&`, `None` if the abort is unconditional.
+    pub fn condition(&self) -> Option<String> {
+        if self.conditions.is_empty() {
+            None
+        } else {
+            Some(self.conditions.join(" && "))
+        }
+    }
+}
+
+/// Where control flows at the edges of the region being structured.
+#[derive(Debug, Clone, Copy, Default)]
+struct Scope {
+    loop_header: Option<CodeOffset>,
+    loop_exit: Option<CodeOffset>,
+    /// Target reached by falling off the end of the region.
+    follow: Option<CodeOffset>,
+}
+
+/// Normalized conditional branch.
+struct Cond {
+    /// Value on the stack which triggers the jump.
+    jump_if: bool,
+    target: CodeOffset,
+    /// Offset of the fall-through code.
+    next: CodeOffset,
+}
+
+struct FnDecompiler<'d, 'a> {
+    d: &'d Decompiler<'a>,
+    code: &'d [Bytecode],
+    params_len: usize,
+    returns_len: usize,
+    /// Basic blocks: start offset -> last instruction offset.
+    blocks: BTreeMap<CodeOffset, CodeOffset>,
+    /// Loop headers: target of backward jump -> offset of the last backward jump.
+    back_edges: HashMap<CodeOffset, CodeOffset>,
+    tmp: usize,
+    /// Conditions of the branches enclosing the current region.
+    path: Vec<Expr>,
+    aborts: Vec<AbortSite>,
+}
+
+impl<'d, 'a> FnDecompiler<'d, 'a> {
+    fn new(
+        d: &'d Decompiler<'a>,
+        code: &'d CodeUnit,
+        params_len: usize,
+        returns_len: usize,
+    ) -> Self {
+        let cfg = VMControlFlowGraph::new(&code.code);
+        let blocks = cfg
+            .blocks()
+            .into_iter()
+            .map(|id| (cfg.block_start(id), cfg.block_end(id)))
+            .collect();
+
+        let mut back_edges = HashMap::new();
+        for (offset, op) in code.code.iter().enumerate() {
+            let offset = offset as CodeOffset;
+            match op {
+                Bytecode::Branch(t) | Bytecode::BrTrue(t) | Bytecode::BrFalse(t)
+                    if *t <= offset =>
+                {
+                    let latch = back_edges.entry(*t).or_insert(offset);
+                    *latch = (*latch).max(offset);
+                }
+                _ => {}
+            }
+        }
+
+        Self {
+            d,
+            code: &code.code,
+            params_len,
+            returns_len,
+            blocks,
+            back_edges,
+            tmp: 0,
+            path: Vec::new(),
+            aborts: Vec::new(),
+        }
+    }
+
+    fn decompile(mut self) -> Result<Vec<Stmt>> {
+        self.run()
+    }
+
+    fn run(&mut self) -> Result<Vec<Stmt>> {
+        let mut stack = Vec::new();
+        let mut stmts = self.region(
+            0,
+            self.code.len() as CodeOffset,
+            &mut stack,
+            Scope::default(),
+        )?;
+        if let Some(Stmt::Exit(s)) = stmts.last() {
+            if s == "return" {
+                stmts.pop();
+            }
+        }
+        Ok(stmts)
+    }
+
+    fn block_end(&self, pc: CodeOffset) -> CodeOffset {
+        self.blocks
+            .range(..=pc)
+            .next_back()
+            .map(|(_, end)| *end)
+            .unwrap_or(pc)
+    }
+
+    fn fresh(&mut self, prefix: &str) -> String {
+        self.tmp += 1;
+        format!("{}{}", prefix, self.tmp - 1)
+    }
+
+    /// Structures the code in `[start, end)`.
+    fn region(
+        &mut self,
+        start: CodeOffset,
+        end: CodeOffset,
+        stack: &mut Vec<Expr>,
+        scope: Scope,
+    ) -> Result<Vec<Stmt>> {
+        let code = self.code;
+        let mut stmts = Vec::new();
+        let mut pc = start;
+
+        while pc < end {
+            let is_current_loop = scope.loop_header == Some(pc) && pc == start;
+            if let Some(latch) = self.back_edges.get(&pc).copied() {
+                if !is_current_loop && latch < end {
+                    let spilled = self.spill(stack);
+                    stmts.extend(spilled);
+                    pc = self.structure_loop(pc, latch + 1, stack, &mut stmts)?;
+                    continue;
+                }
+            }
+
+            let last = self.block_end(pc).min(end - 1);
+            let terminator = &code[last as usize];
+            let is_terminator = matches!(
+                terminator,
+                Bytecode::Branch(_)
+                    | Bytecode::BrTrue(_)
+                    | Bytecode::BrFalse(_)
+                    | Bytecode::Ret
+                    | Bytecode::Abort
+            );
+            let body_end = if is_terminator { last } else { last + 1 };
+            for offset in pc..body_end {
+                self.step(&code[offset as usize], stack, &mut stmts)?;
+            }
+            pc = last + 1;
+
+            match terminator {
+                Bytecode::Ret => {
+                    let mut values = self.pop_n(stack, self.returns_len);
+                    stmts.push(Stmt::Exit(match values.len() {
+                        0 => "return".to_owned(),
+                        1 => format!("return {}", values.remove(0).text),
+                        _ => format!("return ({})", join_exprs(&values)),
+                    }));
+                }
+                Bytecode::Abort => {
+                    let value = pop(stack);
+                    self.aborts.push(AbortSite {
+                        offset: last,
+                        operand: value.text.to_owned(),
+                        conditions: self.path.iter().map(|c| c.wrap(PREC_AND)).collect(),
+                    });
+                    stmts.push(Stmt::Exit(format!("abort {}", value.text)));
+                }
+                Bytecode::Branch(target) => {
+                    let is_last = pc == end;
+                    if let Some(stmt) = self.jump(*target, is_last, pc, scope) {
+                        stmts.push(stmt);
+                    }
+                }
+                Bytecode::BrTrue(_) | Bytecode::BrFalse(_) => {
+                    let cond = self.cond(last);
+                    let value = pop(stack);
+                    // branches can emit statements before the values below are used:
+                    let spilled = self.spill(stack);
+                    stmts.extend(spilled);
+                    pc = self.structure_if(value, cond, end, stack, scope, &mut stmts)?;
+                }
+                _ => {}
+            }
+        }
+
+        Ok(stmts)
+    }
+
+    /// Unconditional jump to the `target` from the end of the block.
+    fn jump(
+        &self,
+        target: CodeOffset,
+        is_last: bool,
+        next: CodeOffset,
+        scope: Scope,
+    ) -> Option<Stmt> {
+        if is_last && Some(target) == scope.follow {
+            None
+        } else if Some(target) == scope.loop_header {
+            Some(Stmt::Exit("continue".to_owned()))
+        } else if Some(target) == scope.loop_exit {
+            Some(Stmt::Exit("break".to_owned()))
+        } else if target == next {
+            None
+        } else {
+            Some(Stmt::Line(format!("/* goto {} */", target)))
+        }
+    }
+
+    /// Merges `BrTrue(then); Branch(else)` pairs into the single conditional jump to `else`.
+    fn cond(&self, offset: CodeOffset) -> Cond {
+        let (jump_if, target) = match &self.code[offset as usize] {
+            Bytecode::BrTrue(t) => (true, *t),
+            Bytecode::BrFalse(t) => (false, *t),
+            _ => unreachable!(),
+        };
+        let next = offset + 1;
+        match self.code.get(next as usize) {
+            Some(Bytecode::Branch(other)) if target == next + 1 && self.is_block_start(next) => {
+                Cond {
+                    jump_if: !jump_if,
+                    target: *other,
+                    next: next + 1,
+                }
+            }
+            _ => Cond {
+                jump_if,
+                target,
+                next,
+            },
+        }
+    }
+
+    fn is_block_start(&self, offset: CodeOffset) -> bool {
+        self.blocks.contains_key(&offset)
+    }
+
+    fn structure_loop(
+        &mut self,
+        header: CodeOffset,
+        exit: CodeOffset,
+        stack: &mut Vec<Expr>,
+        stmts: &mut Vec<Stmt>,
+    ) -> Result<CodeOffset> {
+        let scope = Scope {
+            loop_header: Some(header),
+            loop_exit: Some(exit),
+            follow: Some(header),
+        };
+
+        // `while (cond) { .. }` has nothing but condition in the header block:
+        let code = self.code;
+        let last = self.block_end(header);
+        if matches!(
+            code[last as usize],
+            Bytecode::BrTrue(_) | Bytecode::BrFalse(_)
+        ) {
+            let cond = self.cond(last);
+            let mut header_stack = stack.clone();
+            let mut header_stmts = Vec::new();
+            let tmp = self.tmp;
+            for offset in header..last {
+                self.step(&code[offset as usize], &mut header_stack, &mut header_stmts)?;
+            }
+            if header_stmts.is_empty() && cond.target == exit && cond.next < exit {
+                let value = pop(&mut header_stack);
+                let value = if cond.jump_if { value.not() } else { value };
+                self.path.push(value.clone());
+                let body = self.region(cond.next, exit, &mut header_stack, scope);
+                self.path.pop();
+                let body = body?;
+                stmts.push(Stmt::While(value.text, body));
+                *stack = header_stack;
+                return Ok(exit);
+            }
+            self.tmp = tmp;
+        }
+
+        let body = self.region(header, exit, stack, scope)?;
+        stmts.push(Stmt::Loop(body));
+        Ok(exit)
+    }
+
+    fn structure_if(
+        &mut self,
+        value: Expr,
+        cond: Cond,
+        end: CodeOffset,
+        stack: &mut Vec<Expr>,
+        scope: Scope,
+        stmts: &mut Vec<Stmt>,
+    ) -> Result<CodeOffset> {
+        let jump_cond = if cond.jump_if {
+            value.clone()
+        } else {
+            value.not()
+        };
+
+        // jumps out of the loop:
+        for (target, exit) in &[(scope.loop_exit, "break"), (scope.loop_header, "continue")] {
+            if Some(cond.target) == *target {
+                stmts.push(Stmt::If(
+                    jump_cond.text.to_owned(),
+                    vec![Stmt::Exit(exit.to_string())],
+                    vec![],
+                ));
+                return Ok(cond.next);
+            }
+        }
+
+        if cond.target < cond.next || cond.target > end {
+            stmts.push(Stmt::If(
+                jump_cond.text,
+                vec![Stmt::Line(format!("/* goto {} */", cond.target))],
+                vec![],
+            ));
+            return Ok(cond.next);
+        }
+
+        // then-branch is the fall-through code, else-branch starts at the jump target
+        // and exists if then-branch jumps over it:
+        let then_cond = if cond.jump_if { value.not() } else { value };
+        let (else_start, join) = match self.code.get(cond.target as usize - 1) {
+            Some(Bytecode::Branch(j))
+                if cond.target > cond.next && *j > cond.target && *j <= end =>
+            {
+                (cond.target, *j)
+            }
+            Some(Bytecode::Branch(j))
+                if cond.target > cond.next && *j > end && Some(*j) == scope.follow =>
+            {
+                (cond.target, end)
+            }
+            _ => (cond.target, cond.target),
+        };
+
+        let inner = Scope {
+            follow: Some(join),
+            ..scope
+        };
+        let mut then_stack = stack.clone();
+        let mut else_stack = stack.clone();
+        self.path.push(then_cond.clone());
+        let then_stmts = self.region(cond.next, else_start, &mut then_stack, inner);
+        self.path.pop();
+        self.path.push(then_cond.not());
+        let else_stmts = self.region(else_start, join, &mut else_stack, inner);
+        self.path.pop();
+        let (mut then_stmts, mut else_stmts) = (then_stmts?, else_stmts?);
+
+        let then_exits = then_stmts.last().map(Stmt::is_exit).unwrap_or_default();
+        let else_exits = else_stmts.last().map(Stmt::is_exit).unwrap_or_default();
+        let depth = stack.len();
+
+        if then_exits && !else_exits {
+            *stack = else_stack;
+        } else if else_exits && !then_exits {
+            *stack = then_stack;
+        } else if then_stack.len() == depth + 1
+            && else_stack.len() == depth + 1
+            && then_stmts.is_empty()
+            && else_stmts.is_empty()
+        {
+            // `a && b`, `a || b` or `if (a) b else c` expression:
+            let then_v = then_stack.pop().unwrap();
+            let else_v = else_stack.pop().unwrap();
+            let effect = then_cond.effect || then_v.effect || else_v.effect;
+            let expr = match else_v.text.as_str() {
+                "false" => Expr::new(
+                    format!(
+                        "{} && {}",
+                        then_cond.wrap(PREC_AND),
+                        then_v.wrap(PREC_AND + 1)
+                    ),
+                    PREC_AND,
+                ),
+                "true" => {
+                    let cond = then_cond.not();
+                    Expr::new(
+                        format!("{} || {}", cond.wrap(PREC_OR), then_v.wrap(PREC_OR + 1)),
+                        PREC_OR,
+                    )
+                }
+                _ => Expr::new(
+                    format!(
+                        "if ({}) {} else {}",
+                        then_cond.text, then_v.text, else_v.text
+                    ),
+                    PREC_IF,
+                ),
+            };
+            stack.push(Expr { effect, ..expr });
+            return Ok(join);
+        } else if then_stack.len() == else_stack.len() && then_stack.len() > depth {
+            // values pushed in both branches are spilled into the variables:
+            let count = then_stack.len() - depth;
+            let vars: Vec<_> = (0..count).map(|_| self.fresh("v")).collect();
+            for (branch, branch_stack) in &mut [
+                (&mut then_stmts, &mut then_stack),
+                (&mut else_stmts, &mut else_stack),
+            ] {
+                let values = branch_stack.split_off(depth);
+                for (var, value) in vars.iter().zip(values) {
+                    branch.push(Stmt::Line(format!("{} = {}", var, value.text)));
+                }
+            }
+            for var in vars {
+                stmts.push(Stmt::Line(format!("let {}", var)));
+                stack.push(Expr::atom(var));
+            }
+        } else {
+            *stack = then_stack;
+        }
+
+        // `if (!c) abort e` is the `assert(c, e)`:
+        if else_stmts.is_empty() && then_stmts.len() == 1 {
+            if let Stmt::Exit(s) = &then_stmts[0] {
+                if let Some(code) = s.strip_prefix("abort ") {
+                    let asserted = then_cond.not();
+                    stmts.push(Stmt::Line(format!("assert({}, {})", asserted.text, code)));
+                    return Ok(join);
+                }
+            }
+        }
+
+        if then_stmts.is_empty() && !else_stmts.is_empty() {
+            stmts.push(Stmt::If(then_cond.not().text, else_stmts, vec![]));
+        } else if !then_stmts.is_empty() || !else_stmts.is_empty() {
+            stmts.push(Stmt::If(then_cond.text, then_stmts, else_stmts));
+        }
+        Ok(join)
+    }
+
+    fn pop_n(&self, stack: &mut Vec<Expr>, n: usize) -> Vec<Expr> {
+        let mut values: Vec<_> = (0..n).map(|_| pop(stack)).collect();
+        values.reverse();
+        values
+    }
+
+    fn local(&self, idx: u8) -> String {
+        local_name(idx as usize, self.params_len)
+    }
+
+    fn field(&self, idx: FieldHandleIndex) -> Result<String> {
+        let m = self.d.module()?;
+        let handle = m.field_handle_at(idx);
+        let def = m.struct_def_at(handle.owner);
+        match &def.field_information {
+            StructFieldInformation::Declared(fields) => fields
+                .get(handle.field as usize)
+                .map(|f| m.identifier_at(f.name).to_string())
+                .ok_or_else(|| format_err!("field {} not found", handle.field)),
+            StructFieldInformation::Native => bail!("native struct has no fields"),
+        }
+    }
+
+    /// Name of the struct and names of its fields.
+    fn struct_def(&self, idx: StructDefinitionIndex) -> Result<(String, Vec<String>)> {
+        let m = self.d.module()?;
+        let def = m.struct_def_at(idx);
+        let fields = match &def.field_information {
+            StructFieldInformation::Declared(fields) => fields
+                .iter()
+                .map(|f| m.identifier_at(f.name).to_string())
+                .collect(),
+            StructFieldInformation::Native => vec![],
+        };
+        Ok((self.d.struct_name(def.struct_handle), fields))
+    }
+
+    fn struct_inst(&self, idx: StructDefInstantiationIndex) -> Result<(String, Vec<String>)> {
+        let m = self.d.module()?;
+        let inst = m.struct_instantiation_at(idx);
+        let (name, fields) = self.struct_def(inst.def)?;
+        Ok((
+            format!("{}{}", name, self.d.ty_args_at(inst.type_parameters)),
+            fields,
+        ))
+    }
+
+    fn struct_ty(&self, idx: StructDefinitionIndex) -> Result<String> {
+        self.struct_def(idx).map(|(name, _)| name)
+    }
+
+    fn struct_inst_ty(&self, idx: StructDefInstantiationIndex) -> Result<String> {
+        self.struct_inst(idx).map(|(name, _)| name)
+    }
+
+    fn call(
+        &mut self,
+        name: String,
+        args: usize,
+        returns: usize,
+        stack: &mut Vec<Expr>,
+        stmts: &mut Vec<Stmt>,
+    ) {
+        let args = self.pop_n(stack, args);
+        let call = format!("{}({})", name, join_exprs(&args));
+        match returns {
+            0 => stmts.push(Stmt::Line(call)),
+            1 => stack.push(Expr {
+                path: Some(call.clone()),
+                effect: true,
+                ..Expr::atom(call)
+            }),
+            _ => {
+                let vars: Vec<_> = (0..returns).map(|_| self.fresh("t")).collect();
+                stmts.push(Stmt::Line(format!("let ({}) = {}", vars.join(", "), call)));
+                stack.extend(vars.into_iter().map(Expr::atom));
+            }
+        }
+    }
+
+    /// Executes the instruction keeping the order of side effects: values with effects
+    /// left on the stack are bound to variables before the statements it emits.
+    fn step(&mut self, op: &Bytecode, stack: &mut Vec<Expr>, stmts: &mut Vec<Stmt>) -> Result<()> {
+        let before = stack.clone();
+        let emitted = stmts.len();
+        self.instruction(op, stack, stmts)?;
+
+        // values below the consumed ones are untouched by the instruction:
+        let kept = before
+            .iter()
+            .zip(stack.iter())
+            .take_while(|(a, b)| a.text == b.text)
+            .count();
+        if before[kept..].iter().any(|value| value.effect) {
+            stack[kept..]
+                .iter_mut()
+                .for_each(|value| value.effect = true);
+        }
+        if stmts.len() > emitted {
+            let spilled = self.spill(&mut stack[..kept]);
+            stmts.splice(emitted..emitted, spilled);
+        }
+        Ok(())
+    }
+
+    /// Binds values with effects to fresh variables.
+    fn spill(&mut self, stack: &mut [Expr]) -> Vec<Stmt> {
+        let mut stmts = Vec::new();
+        for value in stack.iter_mut().filter(|value| value.effect) {
+            let var = self.fresh("t");
+            stmts.push(Stmt::Line(format!("let {} = {}", var, value.text)));
+            *value = Expr::atom(var);
+        }
+        stmts
+    }
+
+    fn instruction(
+        &mut self,
+        op: &Bytecode,
+        stack: &mut Vec<Expr>,
+        stmts: &mut Vec<Stmt>,
+    ) -> Result<()> {
+        use Bytecode::*;
+
+        match op {
+            Nop => {}
+            Pop => {
+                let value = pop(stack);
+                stmts.push(Stmt::Line(format!("_ = {}", value.text)));
+            }
+            LdU8(v) => stack.push(Expr::atom(format!("{}u8", v))),
+            LdU64(v) => stack.push(Expr::atom(v.to_string())),
+            LdU128(v) => stack.push(Expr::atom(format!("{}u128", v))),
+            LdTrue => stack.push(Expr::atom("true")),
+            LdFalse => stack.push(Expr::atom("false")),
+            LdConst(idx) => {
+                let constant = self.d.bc.constant_at(*idx);
+                let text = self
+                    .d
+                    .constant(constant)
+                    .unwrap_or_else(|| format!("/* const {} */", idx));
+                stack.push(Expr::atom(text));
+            }
+            CastU8 | CastU64 | CastU128 => {
+                let ty = match op {
+                    CastU8 => "u8",
+                    CastU64 => "u64",
+                    _ => "u128",
+                };
+                let value = pop(stack);
+                stack.push(Expr::atom(format!("({} as {})", value.text, ty)));
+            }
+            CopyLoc(idx) | MoveLoc(idx) => {
+                let name = self.local(*idx);
+                let keyword = if matches!(op, CopyLoc(_)) {
+                    "copy"
+                } else {
+                    "move"
+                };
+                stack.push(Expr {
+                    path: Some(name.clone()),
+                    ..Expr::new(format!("{} {}", keyword, name), PREC_UNARY)
+                });
+            }
+            StLoc(idx) => {
+                let value = pop(stack);
+                stmts.push(Stmt::Line(format!("{} = {}", self.local(*idx), value.text)));
+            }
+            MutBorrowLoc(idx) | ImmBorrowLoc(idx) => {
+                let name = self.local(*idx);
+                let borrow = if matches!(op, MutBorrowLoc(_)) {
+                    "&mut "
+                } else {
+                    "&"
+                };
+                stack.push(Expr {
+                    place: Some(name.clone()),
+                    path: Some(name.clone()),
+                    ..Expr::new(format!("{}{}", borrow, name), PREC_UNARY)
+                });
+            }
+            MutBorrowField(_)
+            | ImmBorrowField(_)
+            | MutBorrowFieldGeneric(_)
+            | ImmBorrowFieldGeneric(_) => {
+                let (idx, borrow) = match op {
+                    MutBorrowField(idx) => (*idx, "&mut "),
+                    ImmBorrowField(idx) => (*idx, "&"),
+                    MutBorrowFieldGeneric(idx) => (
+                        self.d.module()?.field_instantiation_at(*idx).handle,
+                        "&mut ",
+                    ),
+                    ImmBorrowFieldGeneric(idx) => {
+                        (self.d.module()?.field_instantiation_at(*idx).handle, "&")
+                    }
+                    _ => unreachable!(),
+                };
+                let base = pop(stack);
+                let place = format!("{}.{}", base.field_base(), self.field(idx)?);
+                stack.push(Expr {
+                    place: Some(place.clone()),
+                    path: Some(place.clone()),
+                    ..Expr::new(format!("{}{}", borrow, place), PREC_UNARY)
+                });
+            }
+            ReadRef => {
+                let r = pop(stack);
+                stack.push(match r.place {
+                    Some(place) => Expr {
+                        path: Some(place.clone()),
+                        ..Expr::atom(place)
+                    },
+                    None => Expr::new(format!("*{}", r.wrap(PREC_UNARY)), PREC_UNARY),
+                });
+            }
+            WriteRef => {
+                let r = pop(stack);
+                let value = pop(stack);
+                let target = match r.place {
+                    Some(place) => place,
+                    None => format!("*{}", r.wrap(PREC_UNARY)),
+                };
+                stmts.push(Stmt::Line(format!("{} = {}", target, value.text)));
+            }
+            FreezeRef => {
+                let r = pop(stack);
+                stack.push(Expr {
+                    text: format!("freeze({})", r.text),
+                    prec: PREC_ATOM,
+                    ..r
+                });
+            }
+            Call(idx) => {
+                let fh = self.d.bc.function_handle_at(*idx);
+                let name = self
+                    .d
+                    .qualified(fh.module, self.d.bc.identifier_at(fh.name).as_str());
+                let args = self.d.bc.signature_at(fh.parameters).0.len();
+                let returns = self.d.bc.signature_at(fh.return_).0.len();
+                self.call(name, args, returns, stack, stmts);
+            }
+            CallGeneric(idx) => {
+                let fi = self.d.bc.function_instantiation_at(*idx);
+                let fh = self.d.bc.function_handle_at(fi.handle);
+                let name = format!(
+                    "{}{}",
+                    self.d
+                        .qualified(fh.module, self.d.bc.identifier_at(fh.name).as_str()),
+                    self.d.ty_args_at(fi.type_parameters)
+                );
+                let args = self.d.bc.signature_at(fh.parameters).0.len();
+                let returns = self.d.bc.signature_at(fh.return_).0.len();
+                self.call(name, args, returns, stack, stmts);
+            }
+            Pack(_) | PackGeneric(_) => {
+                let (name, fields) = match op {
+                    Pack(idx) => self.struct_def(*idx)?,
+                    PackGeneric(idx) => self.struct_inst(*idx)?,
+                    _ => unreachable!(),
+                };
+                let values = self.pop_n(stack, fields.len());
+                let fields: Vec<_> = fields
+                    .iter()
+                    .zip(values)
+                    .map(|(f, v)| format!("{}: {}", f, v.text))
+                    .collect();
+                let text = if fields.is_empty() {
+                    format!("{} {{}}", name)
+                } else {
+                    format!("{} {{ {} }}", name, fields.join(", "))
+                };
+                stack.push(Expr::atom(text));
+            }
+            Unpack(_) | UnpackGeneric(_) => {
+                let (name, fields) = match op {
+                    Unpack(idx) => self.struct_def(*idx)?,
+                    UnpackGeneric(idx) => self.struct_inst(*idx)?,
+                    _ => unreachable!(),
+                };
+                let value = pop(stack);
+                let vars: Vec<_> = fields.iter().map(|_| self.fresh("t")).collect();
+                let bindings: Vec<_> = fields
+                    .iter()
+                    .zip(vars.iter())
+                    .map(|(f, v)| format!("{}: {}", f, v))
+                    .collect();
+                stmts.push(Stmt::Line(format!(
+                    "let {} {{ {} }} = {}",
+                    name,
+                    bindings.join(", "),
+                    value.text
+                )));
+                stack.extend(vars.into_iter().map(Expr::atom));
+            }
+            Exists(_)
+            | ExistsGeneric(_)
+            | MutBorrowGlobal(_)
+            | MutBorrowGlobalGeneric(_)
+            | ImmBorrowGlobal(_)
+            | ImmBorrowGlobalGeneric(_)
+            | MoveFrom(_)
+            | MoveFromGeneric(_) => {
+                let (func, ty) = match op {
+                    Exists(idx) => ("exists", self.struct_ty(*idx)?),
+                    ExistsGeneric(idx) => ("exists", self.struct_inst_ty(*idx)?),
+                    MutBorrowGlobal(idx) => ("borrow_global_mut", self.struct_ty(*idx)?),
+                    MutBorrowGlobalGeneric(idx) => {
+                        ("borrow_global_mut", self.struct_inst_ty(*idx)?)
+                    }
+                    ImmBorrowGlobal(idx) => ("borrow_global", self.struct_ty(*idx)?),
+                    ImmBorrowGlobalGeneric(idx) => ("borrow_global", self.struct_inst_ty(*idx)?),
+                    MoveFrom(idx) => ("move_from", self.struct_ty(*idx)?),
+                    MoveFromGeneric(idx) => ("move_from", self.struct_inst_ty(*idx)?),
+                    _ => unreachable!(),
+                };
+                let addr = pop(stack);
+                let call = format!("{}<{}>({})", func, ty, addr.text);
+                stack.push(Expr {
+                    path: Some(call.clone()),
+                    effect: func == "move_from" || addr.effect,
+                    ..Expr::atom(call)
+                });
+            }
+            MoveTo(_) | MoveToGeneric(_) => {
+                let ty = match op {
+                    MoveTo(idx) => self.struct_ty(*idx)?,
+                    MoveToGeneric(idx) => self.struct_inst_ty(*idx)?,
+                    _ => unreachable!(),
+                };
+                let value = pop(stack);
+                let signer = pop(stack);
+                stmts.push(Stmt::Line(format!(
+                    "move_to<{}>({}, {})",
+                    ty, signer.text, value.text
+                )));
+            }
+            Not => {
+                let value = pop(stack);
+                stack.push(value.not());
+            }
+            Add | Sub | Mul | Mod | Div | BitOr | BitAnd | Xor | Shl | Shr | Or | And | Eq
+            | Neq | Lt | Gt | Le | Ge => {
+                let (sign, prec) = match op {
+                    Add => ("+", PREC_ADD),
+                    Sub => ("-", PREC_ADD),
+                    Mul => ("*", PREC_MUL),
+                    Mod => ("%", PREC_MUL),
+                    Div => ("/", PREC_MUL),
+                    BitOr => ("|", PREC_BIT_OR),
+                    BitAnd => ("&", PREC_BIT_AND),
+                    Xor => ("^", PREC_XOR),
+                    Shl => ("<<", PREC_SHIFT),
+                    Shr => (">>", PREC_SHIFT),
+                    Or => ("||", PREC_OR),
+                    And => ("&&", PREC_AND),
+                    Eq => ("==", PREC_CMP),
+                    Neq => ("!=", PREC_CMP),
+                    Lt => ("<", PREC_CMP),
+                    Gt => (">", PREC_CMP),
+                    Le => ("<=", PREC_CMP),
+                    Ge => (">=", PREC_CMP),
+                    _ => unreachable!(),
+                };
+                let rhs = pop(stack);
+                let lhs = pop(stack);
+                let lhs_prec = if prec == PREC_CMP { prec + 1 } else { prec };
+                stack.push(Expr::new(
+                    format!("{} {} {}", lhs.wrap(lhs_prec), sign, rhs.wrap(prec + 1)),
+                    prec,
+                ));
+            }
+            Branch(_) | BrTrue(_) | BrFalse(_) | Ret | Abort => {
+                bail!("unexpected terminator {:?}", op)
+            }
+            #[allow(unreachable_patterns)]
+            op => bail!("unsupported instruction {:?}", op),
+        }
+        Ok(())
+    }
+}
+
+fn pop(stack: &mut Vec<Expr>) -> Expr {
+    stack.pop().unwrap_or_else(|| Expr::atom("_"))
+}
+
+fn join_exprs(values: &[Expr]) -> String {
+    values
+        .iter()
+        .map(|v| v.text.as_str())
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+#[cfg(test)]
+mod tests {
+    use libra::vm::file_format::FunctionHandleIndex;
+    use crate::fixtures;
+    use super::*;
+    use Bytecode::*;
+
+    fn decompile_body(m: &CompiledModule, code: Vec<Bytecode>, params_len: usize) -> Vec<String> {
+        let d = Decompiler::new(CompiledMoveRef::Module(m));
+        let code = CodeUnit {
+            locals: SignatureIndex::new(0),
+            code,
+        };
+        let stmts = FnDecompiler::new(&d, &code, params_len, 0)
+            .decompile()
+            .unwrap();
+        stmts.iter().map(|s| s.to_string()).collect()
+    }
+
+    #[test]
+    fn decompile_libra_std() {
+        for name in fixtures::names("modules") {
+            let m = fixtures::module(&name);
+            let src = decompile(CompiledMoveRef::Module(&m), Dialect::Libra)
+                .unwrap_or_else(|err| panic!("{}: {}", name, err));
+            assert!(src.starts_with("address "), "{}", name);
+            assert!(src.contains("\nmodule "), "{}", name);
+        }
+        for name in fixtures::names("scripts") {
+            let s = fixtures::script(&name);
+            let src = decompile(CompiledMoveRef::Script(&s), Dialect::Libra)
+                .unwrap_or_else(|err| panic!("{}: {}", name, err));
+            assert!(src.starts_with("script {"), "{}", name);
+        }
+    }
+
+    #[test]
+    fn decompile_structured() {
+        let vector = fixtures::module("5_Vector");
+        let src = decompile(CompiledMoveRef::Module(&vector), Dialect::Libra).unwrap();
+        assert!(src.contains("while ("), "{}", src);
+        assert!(!src.contains("/* goto"), "{}", src);
+
+        let account = fixtures::module("31_LibraAccount");
+        let src = decompile(CompiledMoveRef::Module(&account), Dialect::Libra).unwrap();
+        assert!(src.contains("assert("), "{}", src);
+        assert!(!src.contains("/* goto"), "{}", src);
+    }
+
+    #[test]
+    fn structure_if_else() {
+        let m = fixtures::module("3_U");
+        let body = decompile_body(
+            &m,
+            vec![
+                CopyLoc(0),
+                BrFalse(5),
+                LdU64(1),
+                StLoc(1),
+                Branch(7),
+                LdU64(2),
+                StLoc(1),
+                Ret,
+            ],
+            1,
+        );
+        assert_eq!(
+            body,
+            vec!["if (copy arg0) {\n    l1 = 1;\n} else {\n    l1 = 2;\n}"]
+        );
+    }
+
+    #[test]
+    fn structure_while() {
+        let m = fixtures::module("3_U");
+        let body = decompile_body(
+            &m,
+            vec![
+                CopyLoc(1),
+                LdU64(10),
+                Lt,
+                BrFalse(9),
+                CopyLoc(1),
+                LdU64(1),
+                Add,
+                StLoc(1),
+                Branch(0),
+                Ret,
+            ],
+            1,
+        );
+        assert_eq!(
+            body,
+            vec!["while (copy l1 < 10) {\n    l1 = copy l1 + 1;\n}"]
+        );
+    }
+
+    #[test]
+    fn structure_loop() {
+        let m = fixtures::module("3_U");
+        let body = decompile_body(
+            &m,
+            vec![LdU64(1), StLoc(1), CopyLoc(0), BrFalse(5), Branch(0), Ret],
+            1,
+        );
+        assert_eq!(
+            body,
+            vec!["loop {\n    l1 = 1;\n    if (copy arg0) {\n        continue;\n    }\n}"]
+        );
+    }
+
+    #[test]
+    fn structure_assert() {
+        let m = fixtures::module("3_U");
+        let body = decompile_body(&m, vec![CopyLoc(0), BrTrue(4), LdU64(7), Abort, Ret], 1);
+        assert_eq!(body, vec!["assert(copy arg0, 7);"]);
+    }
+
+    #[test]
+    fn call_order() {
+        // `let x = f(); g(); _ = x` must not become `g(); _ = f()`:
+        let m = fixtures::module("31_LibraAccount");
+        let d = Decompiler::new(CompiledMoveRef::Module(&m));
+        let find = |returns: usize| {
+            m.function_handles()
+                .iter()
+                .enumerate()
+                .find(|(_, fh)| m.signature_at(fh.return_).0.len() == returns)
+                .map(|(i, fh)| {
+                    let name = d.qualified(fh.module, m.identifier_at(fh.name).as_str());
+                    let args = m.signature_at(fh.parameters).0.len();
+                    (FunctionHandleIndex::new(i as u16), name, args)
+                })
+                .unwrap()
+        };
+        let (f, f_name, f_args) = find(1);
+        let (g, g_name, g_args) = find(0);
+
+        let mut code = vec![LdU64(0); f_args];
+        code.push(Call(f));
+        code.extend(vec![LdU64(0); g_args]);
+        code.extend(vec![Call(g), Pop, Ret]);
+        let body = decompile_body(&m, code, 0);
+
+        assert_eq!(body.len(), 3, "{:?}", body);
+        assert!(
+            body[0].starts_with(&format!("let t0 = {}(", f_name)),
+            "{:?}",
+            body
+        );
+        assert!(body[1].starts_with(&format!("{}(", g_name)), "{:?}", body);
+        assert_eq!(body[2], "_ = t0;");
+    }
+}
@@ -3,6 +3,7 @@ use libra::vm::CompiledModule;
 use libra::vm::errors::BinaryLoaderResult;
 
 pub mod disassembler;
+pub mod decompiler;
 mod uni;
 
 pub use uni::*;
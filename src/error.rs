@@ -0,0 +1,42 @@
+use std::fmt::{Display, Formatter, Result};
+use std::path::PathBuf;
+use libra::vm::errors::PartialVMError;
+use crate::output::utils::path_to_string;
+
+/// Failure on reading input or dependency bytecode.
+#[derive(Debug)]
+pub enum InputError {
+    /// File can't be read.
+    Io(PathBuf, std::io::Error),
+    /// Bytecode can't be adapted to the dialect.
+    Adapt(anyhow::Error),
+    /// Bytecode can't be deserialized.
+    Deserialize(PartialVMError),
+}
+
+impl Display for InputError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result {
+        match self {
+            InputError::Io(path, err) => {
+                write!(f, "unable to read {}: {}", path_to_string(path), err)
+            }
+            InputError::Adapt(err) => write!(f, "unable to adapt bytecode: {}", err),
+            InputError::Deserialize(err) => write!(f, "unable to deserialize bytecode: {:?}", err),
+        }
+    }
+}
+
+impl std::error::Error for InputError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            InputError::Io(_, err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+impl From<PartialVMError> for InputError {
+    fn from(err: PartialVMError) -> Self {
+        InputError::Deserialize(err)
+    }
+}
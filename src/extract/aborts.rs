@@ -0,0 +1,193 @@
+use std::collections::HashMap;
+use std::convert::TryInto;
+use serde::Serialize;
+use libra::bytecode_verifier::control_flow_graph::{ControlFlowGraph, VMControlFlowGraph};
+use libra::vm::file_format::{Bytecode, CodeOffset, CodeUnit, SignatureIndex, SignatureToken};
+use crate::disasm::{decompiler::Decompiler, CompiledMoveRef, MoveAccess};
+
+/// `Abort` instruction of the function body.
+#[derive(Debug, Clone, Eq, PartialEq, Hash, Serialize)]
+pub struct AbortInfo {
+    pub offset: CodeOffset,
+    /// Abort code if it's loaded by constant right before the `Abort`.
+    pub code: Option<u64>,
+    /// Expression of the abort code.
+    pub operand: String,
+    /// Condition leading to the abort, `None` if it's unconditional or unknown.
+    pub condition: Option<String>,
+}
+
+/// Extracts all aborts of the function body.
+/// Constant codes are traced inside the basic block, conditions are taken from the decompiler
+/// if `with_conditions` as it's the most expensive part of the extraction.
+pub fn extract_aborts(
+    bc: &CompiledMoveRef,
+    params: SignatureIndex,
+    returns_len: usize,
+    code: &CodeUnit,
+    with_conditions: bool,
+) -> Vec<AbortInfo> {
+    let sites = if with_conditions {
+        match Decompiler::new(bc.clone()).aborts(params, returns_len, code) {
+            Ok(sites) => sites.into_iter().map(|s| (s.offset, s)).collect(),
+            Err(err) => {
+                debug!("unable to trace abort conditions: {}", err);
+                Default::default()
+            }
+        }
+    } else {
+        HashMap::new()
+    };
+
+    let cfg = VMControlFlowGraph::new(&code.code);
+    let block_starts: Vec<_> = cfg
+        .blocks()
+        .into_iter()
+        .map(|id| cfg.block_start(id))
+        .collect();
+
+    code.code
+        .iter()
+        .enumerate()
+        .filter(|(_, op)| matches!(op, Bytecode::Abort))
+        .map(|(offset, _)| {
+            let offset = offset as CodeOffset;
+            // the operand is loaded in the same block if it's a constant:
+            let abort_code = if offset > 0 && !block_starts.contains(&offset) {
+                const_u64(bc, &code.code[offset as usize - 1])
+            } else {
+                None
+            };
+            let site = sites.get(&offset);
+            let operand = match (abort_code, site) {
+                (Some(value), _) => value.to_string(),
+                (None, Some(site)) => site.operand.to_owned(),
+                (None, None) => "?".to_owned(),
+            };
+            let condition = site.and_then(|site| site.condition());
+            AbortInfo {
+                offset,
+                code: abort_code,
+                operand,
+                condition,
+            }
+        })
+        .collect()
+}
+
+fn const_u64(bc: &CompiledMoveRef, op: &Bytecode) -> Option<u64> {
+    match op {
+        Bytecode::LdU64(value) => Some(*value),
+        Bytecode::LdConst(idx) => {
+            let constant = bc.constant_at(*idx);
+            if constant.type_ == SignatureToken::U64 {
+                constant
+                    .data
+                    .as_slice()
+                    .try_into()
+                    .ok()
+                    .map(u64::from_le_bytes)
+            } else {
+                None
+            }
+        }
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use libra::vm::access::ModuleAccess;
+    use libra::vm::file_format::Bytecode::*;
+    use crate::fixtures;
+    use crate::extract::functions::{extract_functions, FnMap};
+    use super::*;
+
+    fn aborts(code: Vec<Bytecode>, with_conditions: bool) -> (Vec<AbortInfo>, Vec<String>) {
+        let m = fixtures::module("3_U");
+        let params = SignatureIndex::new(0);
+        let params_len = m.signature_at(params).0.len();
+        let names = (0..3)
+            .map(|i| {
+                if i < params_len {
+                    format!("arg{}", i)
+                } else {
+                    format!("l{}", i)
+                }
+            })
+            .collect();
+        let code = CodeUnit {
+            locals: SignatureIndex::new(0),
+            code,
+        };
+        let bc = CompiledMoveRef::Module(&m);
+        (
+            extract_aborts(&bc, params, 0, &code, with_conditions),
+            names,
+        )
+    }
+
+    /// `if (a || b) { if (!c) abort 1 }`
+    fn nested_code() -> Vec<Bytecode> {
+        vec![
+            CopyLoc(0),
+            CopyLoc(1),
+            Or,
+            BrFalse(8),
+            CopyLoc(2),
+            BrTrue(8),
+            LdU64(1),
+            Abort,
+            Ret,
+        ]
+    }
+
+    #[test]
+    fn condition_precedence() {
+        let (aborts, n) = aborts(nested_code(), true);
+        assert_eq!(1, aborts.len());
+        assert_eq!(7, aborts[0].offset);
+        assert_eq!(Some(1), aborts[0].code);
+        assert_eq!("1", aborts[0].operand);
+        assert_eq!(
+            Some(format!(
+                "(copy {} || copy {}) && !copy {}",
+                n[0], n[1], n[2]
+            )),
+            aborts[0].condition
+        );
+    }
+
+    #[test]
+    fn without_conditions() {
+        let (aborts, _) = aborts(nested_code(), false);
+        assert_eq!(1, aborts.len());
+        assert_eq!(Some(1), aborts[0].code);
+        assert_eq!(None, aborts[0].condition);
+    }
+
+    #[test]
+    fn unconditional() {
+        let (aborts, _) = aborts(vec![LdU64(42), Abort], true);
+        assert_eq!(Some(42), aborts[0].code);
+        assert_eq!(None, aborts[0].condition);
+    }
+
+    #[test]
+    fn libra_std_conditions() {
+        let m = fixtures::module("31_LibraAccount");
+        let with = extract_functions(&m, true);
+        assert!(with
+            .values()
+            .flat_map(|f| f.aborts.iter())
+            .any(|a| a.condition.is_some()));
+
+        let without = extract_functions(&m, false);
+        assert!(without
+            .values()
+            .flat_map(|f| f.aborts.iter())
+            .all(|a| a.condition.is_none()));
+        let count = |map: &FnMap| map.values().map(|f| f.aborts.len()).sum::<usize>();
+        assert_eq!(count(&with), count(&without));
+    }
+}
@@ -0,0 +1,34 @@
+use libra::vm::file_format::{Bytecode, CodeUnit, FunctionHandleIndex};
+use crate::disasm::MoveAccess;
+use crate::types::*;
+
+/// Resolves function handle into address of the callee.
+pub fn extract_fn_handle_addr<T: MoveAccess>(bc: &T, idx: FunctionHandleIndex) -> FnAddr {
+    let fh = bc.function_handle_at(idx);
+    let mh = bc.module_handle_at(fh.module);
+    let module = ModAddr::new(
+        bc.address_identifier_at(mh.address).to_owned(),
+        bc.identifier_at(mh.name),
+    );
+    FnAddr::new(module, bc.identifier_at(fh.name))
+}
+
+/// Extracts direct callees of the function body.
+/// Each callee presented once in order of the first call.
+pub fn extract_calls<T: MoveAccess>(bc: &T, code: &CodeUnit) -> Vec<FnAddr> {
+    let mut calls = Vec::new();
+    for op in code.code.iter() {
+        let callee = match op {
+            Bytecode::Call(idx) => extract_fn_handle_addr(bc, *idx),
+            Bytecode::CallGeneric(idx) => {
+                let fi = bc.function_instantiation_at(*idx);
+                extract_fn_handle_addr(bc, fi.handle)
+            }
+            _ => continue,
+        };
+        if !calls.contains(&callee) {
+            calls.push(callee);
+        }
+    }
+    calls
+}
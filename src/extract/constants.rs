@@ -0,0 +1,64 @@
+use std::collections::HashMap;
+use serde::Serialize;
+use libra::vm::file_format::SignatureToken;
+use crate::cli::Dialect;
+use crate::disasm::{CompiledMoveRef, MoveAccess};
+use crate::types::{to_bech32, ConstValue, ModAddr};
+
+/// Constant pools of modules and the script.
+pub type ConstMap = HashMap<ModAddr, Vec<ConstInfo>>;
+
+/// Decoded constant from the constant pool.
+#[derive(Debug, Clone, Eq, PartialEq, Hash, Serialize)]
+pub struct ConstInfo {
+    pub index: usize,
+    /// Move type of the constant.
+    pub ty: String,
+    /// Move literal of the value, raw hex data if it can't be decoded.
+    pub value: String,
+    /// `vector<u8>` as text if it's printable.
+    pub utf8: Option<String>,
+    /// Address as Dfinance wallet address.
+    pub wallet: Option<String>,
+}
+
+pub fn extract_constants(bc: &CompiledMoveRef, dialect: Dialect) -> Vec<ConstInfo> {
+    bc.constant_pool()
+        .iter()
+        .enumerate()
+        .map(|(index, constant)| {
+            let decoded = ConstValue::decode(constant);
+            ConstInfo {
+                index,
+                ty: type_name(&constant.type_),
+                value: decoded
+                    .as_ref()
+                    .map(|v| v.to_literal(dialect))
+                    .unwrap_or_else(|| {
+                        format!("/* malformed */ x\"{}\"", hex::encode(&constant.data))
+                    }),
+                utf8: decoded
+                    .as_ref()
+                    .and_then(ConstValue::as_utf8)
+                    .map(str::to_owned),
+                wallet: match (&decoded, dialect) {
+                    (Some(ConstValue::Address(addr)), Dialect::Dfinance) => Some(to_bech32(addr)),
+                    _ => None,
+                },
+            }
+        })
+        .collect()
+}
+
+/// Constants can only be of primitive types or vectors of them.
+fn type_name(ty: &SignatureToken) -> String {
+    match ty {
+        SignatureToken::Bool => "bool".to_owned(),
+        SignatureToken::U8 => "u8".to_owned(),
+        SignatureToken::U64 => "u64".to_owned(),
+        SignatureToken::U128 => "u128".to_owned(),
+        SignatureToken::Address => "address".to_owned(),
+        SignatureToken::Vector(ty) => format!("vector<{}>", type_name(ty)),
+        other => format!("{:?}", other),
+    }
+}
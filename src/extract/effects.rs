@@ -0,0 +1,183 @@
+use serde::Serialize;
+use libra::vm::file_format::{
+    Bytecode, CodeUnit, CompiledModule, FieldHandleIndex, FieldInstantiationIndex,
+    StructDefinitionIndex, StructDefInstantiationIndex,
+};
+use libra::vm::access::ModuleAccess;
+use crate::types::{IntoModAddr, StructAddr};
+
+/// Resources in the global storage touched by the function.
+#[derive(Debug, Default, Clone, Eq, PartialEq, Hash, Serialize)]
+pub struct StorageEffects {
+    /// `Exists` and `ImmBorrowGlobal`
+    pub reads: Vec<StructAddr>,
+    /// `MutBorrowGlobal`
+    pub mutates: Vec<StructAddr>,
+    /// `MoveTo`
+    pub publishes: Vec<StructAddr>,
+    /// `MoveFrom`
+    pub removes: Vec<StructAddr>,
+}
+
+impl StorageEffects {
+    pub fn is_empty(&self) -> bool {
+        self.reads.is_empty()
+            && self.mutates.is_empty()
+            && self.publishes.is_empty()
+            && self.removes.is_empty()
+    }
+
+    /// Adds effects of the `other` keeping the order of the first occurrence.
+    pub fn merge(&mut self, other: &StorageEffects) {
+        fn merge_into(dst: &mut Vec<StructAddr>, src: &[StructAddr]) {
+            for addr in src {
+                if !dst.contains(addr) {
+                    dst.push(addr.to_owned());
+                }
+            }
+        }
+        merge_into(&mut self.reads, &other.reads);
+        merge_into(&mut self.mutates, &other.mutates);
+        merge_into(&mut self.publishes, &other.publishes);
+        merge_into(&mut self.removes, &other.removes);
+    }
+}
+
+/// Structs handled by value or by reference in the function body.
+#[derive(Debug, Default, Clone, Eq, PartialEq, Hash)]
+pub struct StructOps {
+    /// `Pack`
+    pub packs: Vec<StructAddr>,
+    /// `Unpack`
+    pub unpacks: Vec<StructAddr>,
+    /// `ImmBorrowField` and `MutBorrowField`
+    pub borrows: Vec<StructAddr>,
+}
+
+impl StructOps {
+    pub fn is_empty(&self) -> bool {
+        self.packs.is_empty() && self.unpacks.is_empty() && self.borrows.is_empty()
+    }
+
+    pub fn contains(&self, addr: &StructAddr) -> bool {
+        self.packs.contains(addr) || self.unpacks.contains(addr) || self.borrows.contains(addr)
+    }
+}
+
+/// Resolves struct definition into address of the struct.
+pub fn extract_struct_def_addr(
+    compiled_mod: &CompiledModule,
+    idx: StructDefinitionIndex,
+) -> StructAddr {
+    let struct_def = compiled_mod.struct_def_at(idx);
+    let struct_handle = compiled_mod.struct_handle_at(struct_def.struct_handle);
+    let module_id =
+        compiled_mod.module_id_for_handle(compiled_mod.module_handle_at(struct_handle.module));
+    let name = compiled_mod.identifier_at(struct_handle.name);
+    StructAddr::new(module_id.into_mod_addr(), name)
+}
+
+fn extract_struct_inst_addr(
+    compiled_mod: &CompiledModule,
+    idx: StructDefInstantiationIndex,
+) -> StructAddr {
+    let inst = compiled_mod.struct_instantiation_at(idx);
+    extract_struct_def_addr(compiled_mod, inst.def)
+}
+
+fn extract_field_owner_addr(compiled_mod: &CompiledModule, idx: FieldHandleIndex) -> StructAddr {
+    let field = compiled_mod.field_handle_at(idx);
+    extract_struct_def_addr(compiled_mod, field.owner)
+}
+
+fn extract_field_inst_owner_addr(
+    compiled_mod: &CompiledModule,
+    idx: FieldInstantiationIndex,
+) -> StructAddr {
+    let inst = compiled_mod.field_instantiation_at(idx);
+    extract_field_owner_addr(compiled_mod, inst.handle)
+}
+
+/// Extracts global storage operations of the function body.
+/// Scripts can't access the global storage directly, so only modules are supported.
+pub fn extract_effects(compiled_mod: &CompiledModule, code: &CodeUnit) -> StorageEffects {
+    use Bytecode::*;
+
+    let mut effects = StorageEffects::default();
+    for op in code.code.iter() {
+        let (target, addr) = match op {
+            Exists(idx) | ImmBorrowGlobal(idx) => (
+                &mut effects.reads,
+                extract_struct_def_addr(compiled_mod, *idx),
+            ),
+            ExistsGeneric(idx) | ImmBorrowGlobalGeneric(idx) => (
+                &mut effects.reads,
+                extract_struct_inst_addr(compiled_mod, *idx),
+            ),
+            MutBorrowGlobal(idx) => (
+                &mut effects.mutates,
+                extract_struct_def_addr(compiled_mod, *idx),
+            ),
+            MutBorrowGlobalGeneric(idx) => (
+                &mut effects.mutates,
+                extract_struct_inst_addr(compiled_mod, *idx),
+            ),
+            MoveTo(idx) => (
+                &mut effects.publishes,
+                extract_struct_def_addr(compiled_mod, *idx),
+            ),
+            MoveToGeneric(idx) => (
+                &mut effects.publishes,
+                extract_struct_inst_addr(compiled_mod, *idx),
+            ),
+            MoveFrom(idx) => (
+                &mut effects.removes,
+                extract_struct_def_addr(compiled_mod, *idx),
+            ),
+            MoveFromGeneric(idx) => (
+                &mut effects.removes,
+                extract_struct_inst_addr(compiled_mod, *idx),
+            ),
+            _ => continue,
+        };
+        if !target.contains(&addr) {
+            target.push(addr);
+        }
+    }
+    effects
+}
+
+/// Extracts struct operations of the function body.
+/// Structs can be packed, unpacked and borrowed only inside the declaring module.
+pub fn extract_struct_ops(compiled_mod: &CompiledModule, code: &CodeUnit) -> StructOps {
+    use Bytecode::*;
+
+    let mut ops = StructOps::default();
+    for op in code.code.iter() {
+        let (target, addr) = match op {
+            Pack(idx) => (&mut ops.packs, extract_struct_def_addr(compiled_mod, *idx)),
+            PackGeneric(idx) => (&mut ops.packs, extract_struct_inst_addr(compiled_mod, *idx)),
+            Unpack(idx) => (
+                &mut ops.unpacks,
+                extract_struct_def_addr(compiled_mod, *idx),
+            ),
+            UnpackGeneric(idx) => (
+                &mut ops.unpacks,
+                extract_struct_inst_addr(compiled_mod, *idx),
+            ),
+            ImmBorrowField(idx) | MutBorrowField(idx) => (
+                &mut ops.borrows,
+                extract_field_owner_addr(compiled_mod, *idx),
+            ),
+            ImmBorrowFieldGeneric(idx) | MutBorrowFieldGeneric(idx) => (
+                &mut ops.borrows,
+                extract_field_inst_owner_addr(compiled_mod, *idx),
+            ),
+            _ => continue,
+        };
+        if !target.contains(&addr) {
+            target.push(addr);
+        }
+    }
+    ops
+}
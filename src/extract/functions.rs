@@ -1,29 +1,47 @@
 use std::collections::HashMap;
-use libra::vm::file_format::{CompiledModule, CodeUnit, CompiledScript};
+use libra::bytecode_source_map::source_map::SourceMap;
+use libra::move_ir_types::location::Loc;
+use libra::vm::file_format::{CompiledModule, CodeUnit, CompiledScript, FunctionDefinitionIndex};
 use libra::vm::access::ModuleAccess;
 use libra::vm::access::ScriptAccess;
 use crate::{
-    disasm::{default_script_fn_address, default_script_address},
+    disasm::{default_script_fn_address, default_script_address, CompiledMoveRef},
     types::{
-        extract_ty, Ty, FnAddr, ModAddr, IntoModAddr, TypeParamKind, extract_type_param_kind,
-        StructAddr, extract_ty_scrpt,
+        extract_ty, Ty, FnAddr, ModAddr, TypeParamKind, extract_type_param_kind, StructAddr,
+        extract_ty_scrpt,
     },
 };
+use super::calls::extract_calls;
+use super::effects::{
+    extract_effects, extract_struct_def_addr, extract_struct_ops, StorageEffects, StructOps,
+};
+use super::aborts::{extract_aborts, AbortInfo};
 
 #[derive(Debug, Clone, Eq, PartialEq)]
 pub struct FunctionInfo {
     pub parameters: Vec<Ty>,
+    /// Names of parameters, known only if the source map is provided.
+    pub parameter_names: Vec<String>,
     pub type_parameters: Vec<TypeParamKind>,
     pub returns: Vec<Ty>,
     pub acquires: Vec<StructAddr>,
     pub is_public: bool,
     pub is_native: bool,
     pub code: Option<CodeUnit>,
+    pub calls: Vec<FnAddr>,
+    pub effects: StorageEffects,
+    pub struct_ops: StructOps,
+    pub aborts: Vec<AbortInfo>,
 }
 
 pub type FnMap = HashMap<FnAddr, FunctionInfo>;
 
-pub fn extract_functions(compiled_mod: &CompiledModule) -> FnMap {
+/// Source map produced by the Move compiler alongside the bytecode (`.mvsm`).
+pub type MoveSourceMap = SourceMap<Loc>;
+
+/// Extracts functions of the module.
+/// Abort conditions require decompilation of the bodies, so they are traced only if requested.
+pub fn extract_functions(compiled_mod: &CompiledModule, abort_conditions: bool) -> FnMap {
     let mut functions_map = HashMap::new();
     for function_def in compiled_mod.function_defs() {
         let function_handle = compiled_mod.function_handle_at(function_def.function);
@@ -42,23 +60,47 @@ pub fn extract_functions(compiled_mod: &CompiledModule) -> FnMap {
             .iter()
             .map(|param| extract_type_param_kind(param.clone()))
             .collect();
-        let returns = compiled_mod
+        let returns: Vec<_> = compiled_mod
             .signature_at(function_handle.return_)
             .0
             .iter()
             .map(|ty| extract_ty(ty, compiled_mod))
             .collect();
 
-        let mut acqs = vec![];
-        for acq in function_def.acquires_global_resources.clone() {
-            let struct_def = compiled_mod.struct_def_at(acq);
-            let struct_handle = compiled_mod.struct_handle_at(struct_def.struct_handle);
-            let module_id = compiled_mod
-                .module_id_for_handle(compiled_mod.module_handle_at(struct_handle.module));
-            let name = compiled_mod.identifier_at(struct_handle.name);
-            let acq_struct_addr = StructAddr::new(module_id.into_mod_addr(), name);
-            acqs.push(acq_struct_addr)
-        }
+        let acqs = function_def
+            .acquires_global_resources
+            .iter()
+            .map(|acq| extract_struct_def_addr(compiled_mod, *acq))
+            .collect();
+
+        let calls = function_def
+            .code
+            .as_ref()
+            .map(|code| extract_calls(&CompiledMoveRef::from(compiled_mod), code))
+            .unwrap_or_default();
+        let effects = function_def
+            .code
+            .as_ref()
+            .map(|code| extract_effects(compiled_mod, code))
+            .unwrap_or_default();
+        let struct_ops = function_def
+            .code
+            .as_ref()
+            .map(|code| extract_struct_ops(compiled_mod, code))
+            .unwrap_or_default();
+        let aborts = function_def
+            .code
+            .as_ref()
+            .map(|code| {
+                extract_aborts(
+                    &CompiledMoveRef::from(compiled_mod),
+                    function_handle.parameters,
+                    returns.len(),
+                    code,
+                    abort_conditions,
+                )
+            })
+            .unwrap_or_default();
 
         let is_public = function_def.is_public();
         let is_native = function_def.is_native();
@@ -67,31 +109,142 @@ pub fn extract_functions(compiled_mod: &CompiledModule) -> FnMap {
             fn_addr,
             FunctionInfo {
                 parameters,
+                parameter_names: Default::default(),
                 type_parameters,
                 returns,
                 acquires: acqs,
                 is_public,
                 is_native,
                 code: function_def.code.clone(),
+                calls,
+                effects,
+                struct_ops,
+                aborts,
             },
         );
     }
     functions_map
 }
 
+/// Names of the script parameters from the `source_map`,
+/// `argN` if the map doesn't describe the script.
+fn script_parameter_names<L: Clone + Eq>(
+    bc: &CompiledScript,
+    source_map: &SourceMap<L>,
+    count: usize,
+) -> Vec<String> {
+    // script's main is the only function in the source map, parameters go first in locals:
+    let locals = bc.signature_at(bc.code().locals).0.len();
+    match source_map.get_function_source_map(FunctionDefinitionIndex(0)) {
+        Ok(fn_map) if fn_map.locals.len() == count + locals => fn_map
+            .locals
+            .iter()
+            .take(count)
+            .map(|(name, _)| name.to_owned())
+            .collect(),
+        Ok(fn_map) => {
+            warn!(
+                "Source map doesn't match the script: {} locals are mapped, but it has {}",
+                fn_map.locals.len(),
+                count + locals
+            );
+            (0..count).map(|i| format!("arg{}", i)).collect()
+        }
+        Err(err) => {
+            warn!("Source map doesn't describe the script: {}", err);
+            (0..count).map(|i| format!("arg{}", i)).collect()
+        }
+    }
+}
+
 pub fn extract_script_main_fn(
     bc: &CompiledScript,
+    source_map: Option<&MoveSourceMap>,
 ) -> impl IntoIterator<Item = (FnAddr, FunctionInfo)> {
+    let script = bc.as_inner();
+    let parameters: Vec<_> = bc
+        .signature_at(script.parameters)
+        .0
+        .iter()
+        .map(|ty| extract_ty_scrpt(ty, bc))
+        .collect();
+    let type_parameters = script
+        .type_parameters
+        .iter()
+        .map(|kind| extract_type_param_kind(kind.clone()))
+        .collect();
+
+    let parameter_names = source_map
+        .map(|map| script_parameter_names(bc, map, parameters.len()))
+        .unwrap_or_default();
+
     vec![(
         default_script_fn_address().into(),
         FunctionInfo {
-            parameters: Default::default(),
-            type_parameters: Default::default(),
+            parameters,
+            parameter_names,
+            type_parameters,
             returns: Default::default(),
             acquires: Default::default(),
             is_public: true,
             is_native: false,
             code: Some(bc.code().to_owned()),
+            calls: extract_calls(&CompiledMoveRef::from(bc), bc.code()),
+            effects: Default::default(),
+            struct_ops: Default::default(),
+            aborts: extract_aborts(
+                &CompiledMoveRef::from(bc),
+                script.parameters,
+                0,
+                bc.code(),
+                true,
+            ),
         },
     )]
 }
+
+#[cfg(test)]
+mod tests {
+    use crate::fixtures;
+    use super::*;
+
+    /// Source map of the script main with the `locals` names, locations are omitted.
+    fn source_map(locals: &[String]) -> SourceMap<()> {
+        let mut map = SourceMap::new(None);
+        map.add_top_level_function_mapping(FunctionDefinitionIndex(0), ())
+            .unwrap();
+        for name in locals {
+            map.add_local_mapping(FunctionDefinitionIndex(0), (name.to_owned(), ()))
+                .unwrap();
+        }
+        map
+    }
+
+    #[test]
+    fn script_parameter_names_from_source_map() {
+        let script = fixtures::script("peer_to_peer_with_metadata");
+        let count = script.signature_at(script.as_inner().parameters).0.len();
+        let locals = script.signature_at(script.code().locals).0.len();
+        let names: Vec<_> = (0..count + locals)
+            .map(|i| format!("local_{}", i))
+            .collect();
+
+        assert_eq!(
+            script_parameter_names(&script, &source_map(&names), count),
+            names[..count].to_vec()
+        );
+
+        // map of another script:
+        let mut other = names.to_owned();
+        other.push("extra".to_owned());
+        let args: Vec<_> = (0..count).map(|i| format!("arg{}", i)).collect();
+        assert_eq!(
+            script_parameter_names(&script, &source_map(&other), count),
+            args
+        );
+        assert_eq!(
+            script_parameter_names(&script, &SourceMap::<()>::new(None), count),
+            args
+        );
+    }
+}
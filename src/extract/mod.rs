@@ -16,6 +16,10 @@ pub mod mod_handles;
 pub mod fn_handles;
 pub mod struct_map;
 pub mod functions;
+pub mod calls;
+pub mod effects;
+pub mod aborts;
+pub mod constants;
 
 pub mod prelude {
     pub use super::{Extract, ExtractRef, ExtractMut, ExtractFrom, ExtractWith};
@@ -26,6 +30,10 @@ pub mod prelude {
     pub use super::struct_map::*;
     pub use super::fn_handles::*;
     pub use super::functions::*;
+    pub use super::calls::*;
+    pub use super::effects::*;
+    pub use super::aborts::*;
+    pub use super::constants::*;
 }
 
 pub trait Extract<T> {
@@ -14,7 +14,7 @@ impl Extract<ModAddr> for CompiledModule {
 
 impl Extract<ModAddr> for ModInfo {
     fn extract(&self) -> ModAddr {
-        (self.address(), self.name()).into_mod_addr()
+        self.mod_addr()
     }
 }
 
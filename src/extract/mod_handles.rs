@@ -37,7 +37,7 @@ pub fn extract_module_mod_handles(bc: &CompiledModule) -> Vec<ModAddr> {
     for mh in bc.as_inner().module_handles.iter() {
         let name = bc.identifier_at(mh.name);
         let address = bc.address_identifier_at(mh.address);
-        if name != self_name && address != self_address {
+        if name != self_name || address != self_address {
             deps.push((address, name).into())
         }
     }
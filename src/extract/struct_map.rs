@@ -10,7 +10,7 @@ use crate::types::{ModAddr, StructAddr, Ty, extract_ty, TypeParamKind, extract_t
 
 pub type StructMap = HashMap<StructAddr, StructInfo>;
 
-#[derive(Debug, Clone, Serialize)]
+#[derive(Debug, Clone, Eq, PartialEq, Serialize)]
 pub enum StructKind {
     HasResourceAsType,
     Resource,
@@ -0,0 +1,122 @@
+//! Libra standard library bytecode and temporary directories shared by the unit tests.
+//! Fixtures are compiled for 16-byte addresses, so they are adapted on load.
+
+use std::path::PathBuf;
+use compat::AddressWidth;
+use libra::vm::file_format::{
+    Bytecode, CompiledModule, CompiledModuleMut, CompiledScript, FunctionHandleIndex,
+    StructDefinitionIndex,
+};
+
+fn dir(kind: &str) -> PathBuf {
+    PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+        .join("compat/tests/libra_std")
+        .join(kind)
+}
+
+/// Raw bytecode of the fixture, e.g. `modules/5_Vector`.
+pub fn raw(name: &str) -> Vec<u8> {
+    let path = dir("").join(format!("{}.mv", name));
+    std::fs::read(&path).unwrap_or_else(|err| panic!("{}: {}", path.display(), err))
+}
+
+/// Bytecode of the fixture adapted to the dfinance address width.
+pub fn adapted(name: &str) -> Vec<u8> {
+    let mut bytes = raw(name);
+    compat::adapt_to(&mut bytes, AddressWidth::Dfinance).unwrap();
+    bytes
+}
+
+pub fn module(name: &str) -> CompiledModule {
+    CompiledModule::deserialize(&adapted(&format!("modules/{}", name))).unwrap()
+}
+
+pub fn script(name: &str) -> CompiledScript {
+    CompiledScript::deserialize(&adapted(&format!("scripts/{}", name))).unwrap()
+}
+
+/// Copy of the fixture module with bodies of the named functions replaced.
+/// `body` gets handles of functions and definitions of structs by name,
+/// bodies aren't verified, so the stack doesn't need to be balanced.
+pub fn with_bodies<F>(name: &str, functions: &[&str], body: F) -> CompiledModule
+where
+    F: Fn(
+        &str,
+        &dyn Fn(&str) -> FunctionHandleIndex,
+        &dyn Fn(&str) -> StructDefinitionIndex,
+    ) -> Vec<Bytecode>,
+{
+    let mut m: CompiledModuleMut = module(name).into_inner();
+    let ident = |m: &CompiledModuleMut, idx: usize| m.identifiers[idx].as_str().to_owned();
+    // functions of the module itself, imported ones can share names:
+    let handles: Vec<_> = m
+        .function_handles
+        .iter()
+        .map(|h| {
+            if h.module == m.self_module_handle_idx {
+                Some(ident(&m, h.name.0 as usize))
+            } else {
+                None
+            }
+        })
+        .collect();
+    let structs: Vec<_> = m
+        .struct_defs
+        .iter()
+        .map(|def| {
+            ident(
+                &m,
+                m.struct_handles[def.struct_handle.0 as usize].name.0 as usize,
+            )
+        })
+        .collect();
+    let function = |name: &str| {
+        let idx = handles
+            .iter()
+            .position(|h| h.as_deref() == Some(name))
+            .unwrap_or_else(|| panic!("{}", name));
+        FunctionHandleIndex(idx as u16)
+    };
+    let struct_def = |name: &str| {
+        let idx = structs
+            .iter()
+            .position(|s| s == name)
+            .unwrap_or_else(|| panic!("{}", name));
+        StructDefinitionIndex(idx as u16)
+    };
+
+    for name in functions {
+        let handle = function(name);
+        let def = m
+            .function_defs
+            .iter_mut()
+            .find(|def| def.function == handle)
+            .unwrap_or_else(|| panic!("{} is not defined", name));
+        def.code
+            .as_mut()
+            .unwrap_or_else(|| panic!("{} is native", name))
+            .code = body(name, &function, &struct_def);
+    }
+    m.freeze().unwrap()
+}
+
+/// Names of all fixtures of the `kind` (`modules` or `scripts`) in order.
+pub fn names(kind: &str) -> Vec<String> {
+    let mut names: Vec<_> = std::fs::read_dir(dir(kind))
+        .unwrap()
+        .filter_map(|e| e.ok())
+        .map(|e| e.path())
+        .filter(|p| p.extension().map(|ext| ext == "mv").unwrap_or_default())
+        .filter_map(|p| p.file_stem().map(|s| s.to_string_lossy().into_owned()))
+        .collect();
+    names.sort();
+    names
+}
+
+/// Empty temporary directory of the test `name`, it's created by the caller if needed.
+pub fn temp_dir(name: &str) -> PathBuf {
+    let dir =
+        std::env::temp_dir().join(format!("move-unscrambler-{}-{}", name, std::process::id()));
+    std::fs::remove_dir_all(&dir).ok();
+    dir
+}
@@ -12,6 +12,7 @@ extern crate log;
 extern crate clap;
 
 mod cli;
+mod error;
 // mod cfg;
 mod deps;
 mod types;
@@ -20,24 +21,39 @@ mod extract;
 mod data;
 mod analyse;
 mod output;
+#[cfg(test)]
+mod fixtures;
 
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
 use anyhow::{bail, format_err, Result};
-use cli::InputType;
-use disasm::{default_script_fn_address, CompiledMove};
+use compat::AddressWidth;
+use libra::vm::CompiledModule;
+use cli::{Dialect, InputType};
+use disasm::{default_script_fn_address, decompiler, CompiledMove, CompiledMoveRef, MoveAccess};
 use deps::map::ModMap;
 use deps::map::{DependencyMap, AsMap};
 use deps::resolver::UnresolvedMap;
+use analyse::dialect::InferredDialect;
+use analyse::focus::Focus;
+use analyse::upgrade::UpgradeDiff;
+use error::InputError;
 use types::MoveType;
-use types::{FnAddr, ModAddr};
+use types::{FnAddr, IntoModAddr, ModAddr, StructAddr};
 use extract::prelude::*;
 use output::{
-    ctx::{Ctx, IntoContext},
+    ctx::{Ctx, IndexCtx, IndexEntry, IntoContext, SourceEntry},
     utils::path_to_string,
 };
 use data::{DbRoot, Db};
 
 fn main() {
-    let opts = validate_config(cli::init());
+    let opts = match cli::init().and_then(purge_cache) {
+        // nothing to analyse, only the cache purge requested:
+        Ok(opts) if opts.input.online.purge_cache && opts.input.offline.path.is_none() => return,
+        opts => opts.and_then(validate_config),
+    };
 
     match opts {
         Ok(opts) => run(opts),
@@ -46,36 +62,82 @@ fn main() {
     trace!("♥️");
 }
 
+fn purge_cache(opts: cli::Opts) -> Result<cli::Opts> {
+    if opts.input.online.purge_cache {
+        match opts.input.online.cache() {
+            Some(cache) => cache.purge()?,
+            None => warn!("Cache is disabled by --no-cache, nothing to purge"),
+        }
+    }
+    Ok(opts)
+}
+
 fn validate_config(mut opts: cli::Opts) -> Result<cli::Opts> {
     use std::fs::read_dir;
     use std::fs::create_dir_all;
     use std::fs::canonicalize;
 
-    opts.input.offline.path = canonicalize(&opts.input.offline.path)?;
+    opts.input.offline.path = match &opts.input.offline.path {
+        // not existing path is a glob pattern:
+        Some(path) if !path.exists() => Some(path.to_owned()),
+        Some(path) => Some(canonicalize(path)?),
+        None => {
+            bail!("Input file is required, pass it with --input or set `input.path` in the config")
+        }
+    };
 
     for dep in opts.input.offline.dependencies.iter_mut() {
         *dep = canonicalize(&dep)?;
     }
 
-    create_dir_all(&opts.output.dir)?;
-    opts.output.dir = canonicalize(&opts.output.dir)?;
+    if let Some(path) = opts.input.offline.source_map.as_mut() {
+        *path = canonicalize(&path)?;
+    }
+
+    if let Some(path) = opts.input.offline.upgrade_from.as_mut() {
+        *path = canonicalize(&path)?;
+    }
+
+    if let Some(dir) = opts.output.templates.as_mut() {
+        *dir = canonicalize(&dir)?;
+    }
 
-    // TODO: read_dir for output dir & opts.output.force
+    if opts.output.is_stdout() {
+        opts.output.inline = true;
+    } else {
+        create_dir_all(&opts.output.dir)?;
+        opts.output.dir = canonicalize(&opts.output.dir)?;
+
+        if !opts.output.force && read_dir(&opts.output.dir)?.next().is_some() {
+            bail!(
+                "Output directory '{}' is not empty. Use --force to override existing files.",
+                path_to_string(&opts.output.dir)
+            );
+        }
+    }
 
     {
         use cli::Dialect;
 
         if opts.input.online.offline {
-            info!("Offline mode requested, so passes node (data-source) URI will be ignored");
-            for ds in opts.input.online.ds.drain(..) {
-                debug!("\t\t- {}", ds);
+            if opts.input.online.no_cache {
+                info!("Offline mode requested, so passes node (data-source) URI will be ignored");
+                for ds in opts.input.online.ds.drain(..) {
+                    debug!("\t\t- {}", ds);
+                }
+            } else {
+                info!("Offline mode requested, so dependencies are resolved from the cache only");
+            }
+            if opts.input.online.refresh_cache {
+                warn!("Cache can't be refreshed in offline mode, cached modules are used");
+                opts.input.online.refresh_cache = false;
             }
         } else {
             match (opts.input.online.ds.len(), opts.input.dialect) {
-                (0, Dialect::Libra) => {
+                (0, Some(Dialect::Libra)) | (0, None) => {
                     info!("Offline mode turned on 'cause of node URI is missed.")
                 }
-                (0, Dialect::Dfinance) => {
+                (0, Some(Dialect::Dfinance)) => {
                     info!("Offline mode turned on 'cause of node URI is missed.")
                     // TODO: set up default endpoint URI? Are we should share own node?
                     // opts.input.ds = Some("?");
@@ -85,15 +147,283 @@ fn validate_config(mut opts: cli::Opts) -> Result<cli::Opts> {
         }
     }
 
+    if let Some(focus) = &opts.output.focus {
+        // functions and structs can only be told apart by the analysed bytecode:
+        focus.parse::<StructAddr>()?;
+    }
+
     trace!("cfg & env validated");
     Ok(opts)
 }
 
 fn run(opts: cli::Opts) {
-    let (input_type, input, input_deps) = read_input(&opts);
-    let detected_input_type = input.extract();
-    let (deps, missed_deps) = read_deps(&opts.input, &input_deps);
+    let inputs = match list_inputs(&opts.input.offline) {
+        Ok(inputs) => inputs,
+        Err(err) => {
+            error!("{}", err);
+            std::process::exit(1);
+        }
+    };
+    let batch = inputs.len() > 1;
+    if batch && opts.output.is_stdout() {
+        error!(
+            "Batch of {} inputs requires the output directory",
+            inputs.len()
+        );
+        std::process::exit(1);
+    }
+
+    // a broken input of the batch is listed in the index, the rest is analysed:
+    let mut reports = Vec::new();
+    let mut readable = Vec::new();
+    for path in inputs {
+        match read_input(&opts, &path) {
+            Ok(input) => readable.push((path, input)),
+            Err(err) if batch => {
+                warn!("broken input {}: {}", path_to_string(&path), err);
+                reports.push(IndexEntry {
+                    input: path_to_string(&path),
+                    address: None,
+                    is_script: false,
+                    report: None,
+                    rejected: false,
+                    error: Some(err.to_string()),
+                });
+            }
+            Err(err) => {
+                error!("{}", err);
+                std::process::exit(1);
+            }
+        }
+    }
+    let inputs = readable;
 
+    // modules of the batch are dependencies of each other:
+    let siblings: Vec<(PathBuf, CompiledModule)> = if batch {
+        inputs
+            .iter()
+            .filter_map(|(path, (_, input, _, _))| match input {
+                CompiledMove::Module(bc) => Some((path.to_owned(), bc.to_owned())),
+                CompiledMove::Script(_) => None,
+            })
+            .collect()
+    } else {
+        Vec::new()
+    };
+
+    // dependencies are resolved once for all inputs
+    let mut input_deps: Vec<ModAddr> = inputs
+        .iter()
+        .flat_map(|(_, (_, _, deps, _))| deps.iter().cloned())
+        .filter(|addr| {
+            !siblings
+                .iter()
+                .any(|(_, bc)| &bc.self_id().into_mod_addr() == addr)
+        })
+        .collect();
+    input_deps.sort_by_key(|addr| format!("{:#x}", addr));
+    input_deps.dedup();
+    let (deps, missed_deps) = read_deps(&opts.input, siblings, &input_deps);
+    let deps = Arc::new(deps);
+
+    let previous = match read_previous_version(&opts) {
+        Ok(previous) => previous,
+        Err(err) => {
+            error!("{}", err);
+            std::process::exit(1);
+        }
+    };
+
+    let mut dialects = HashSet::new();
+    let mut dirs = HashSet::new();
+    for (path, (input_type, input, _, inferred)) in inputs {
+        let dialect = resolve_dialect(&opts, &path, inferred);
+        dialects.insert(dialect);
+        let output = if batch {
+            let stem = path
+                .file_stem()
+                .map(|s| s.to_string_lossy().into_owned())
+                .unwrap_or_default();
+            let mut name = stem.to_owned();
+            let mut n = 1;
+            while !dirs.insert(name.to_owned()) {
+                name = format!("{}-{}", stem, n);
+                n += 1;
+            }
+            opts.output.nested(&name)
+        } else {
+            opts.output.clone()
+        };
+
+        let upgrade = match (&previous, &input) {
+            (Some(old), CompiledMove::Module(new)) if old.self_id() == new.self_id() => {
+                Some(analyse::upgrade::diff_modules(old, new))
+            }
+            (Some(_), CompiledMove::Script(_)) if !batch => {
+                error!("Upgrade compatibility can only be checked for modules");
+                std::process::exit(1);
+            }
+            (Some(old), _) if !batch => {
+                error!(
+                    "Previous version is the module {:#x}, but the input is {:#x}",
+                    old.self_id().into_mod_addr(),
+                    input.extract::<ModAddr>()
+                );
+                std::process::exit(1);
+            }
+            (Some(old), _) => {
+                warn!(
+                    "{}: previous version is the module {:#x}, but the input is {:#x}, upgrade is not checked",
+                    path_to_string(&path),
+                    old.self_id().into_mod_addr(),
+                    input.extract::<ModAddr>()
+                );
+                None
+            }
+            (None, _) => None,
+        };
+        // source map describes the only script:
+        let source_map = if batch { None } else { read_source_map(&opts) };
+
+        let mut db = analyse_input(
+            &opts,
+            input_type,
+            input,
+            dialect,
+            Arc::clone(&deps),
+            &missed_deps,
+            upgrade,
+            source_map,
+        );
+        if let Some(target) = &opts.output.focus {
+            match Focus::resolve(target, &db.functions, &db.structs) {
+                Ok(focus) => analyse::focus::apply(&mut db, &focus),
+                Err(err) if batch => {
+                    warn!("{}: {}, report is not focused", path_to_string(&path), err)
+                }
+                Err(err) => {
+                    error!("{}", err);
+                    std::process::exit(1);
+                }
+            }
+        }
+        let address: ModAddr = db.root.bc.extract();
+        let report = IndexEntry {
+            input: path_to_string(&path),
+            rejected: db.findings.iter().any(|f| f.module == address),
+            address: Some(address),
+            is_script: matches!(db.root.kind, MoveType::Script),
+            report: Some(
+                output
+                    .dir
+                    .strip_prefix(&opts.output.dir)
+                    .unwrap_or(&output.dir)
+                    .join(output::tmt::report_file_name(&output.format))
+                    .to_string_lossy()
+                    .into_owned(),
+            ),
+            error: None,
+        };
+
+        render(&output, db);
+        reports.push(report);
+    }
+
+    if batch {
+        // addresses of mixed dialects are shown in hex:
+        let dialect = match dialects.len() {
+            1 => dialects.into_iter().next().unwrap_or_default(),
+            _ => Dialect::Libra,
+        };
+        if let Err(err) = output::tmt::render_index(&opts.output, dialect, IndexCtx { reports }) {
+            error!("{}", err);
+            std::process::exit(1);
+        }
+    }
+}
+
+/// Dialect passed in the command line or inferred from the input.
+fn resolve_dialect(opts: &cli::Opts, path: &Path, inferred: Option<InferredDialect>) -> Dialect {
+    if let Some(inferred) = &inferred {
+        if let Some(conflict) = &inferred.conflict {
+            warn!(
+                "{} looks like {:?} ({}), but it {}.",
+                path_to_string(path),
+                inferred.dialect,
+                inferred.reason,
+                conflict
+            );
+        }
+    }
+    match (opts.input.dialect, inferred) {
+        (Some(dialect), Some(inferred)) if dialect != inferred.dialect => {
+            warn!(
+                "Dialect {:?} is requested, but {} looks like {:?} ({}). Addresses can be garbled.",
+                dialect,
+                path_to_string(path),
+                inferred.dialect,
+                inferred.reason
+            );
+            dialect
+        }
+        (Some(dialect), _) => dialect,
+        (None, Some(inferred)) => {
+            info!(
+                "dialect inferred for {}: {:?} ({})",
+                path_to_string(path),
+                inferred.dialect,
+                inferred.reason
+            );
+            inferred.dialect
+        }
+        (None, None) => {
+            let dialect = Dialect::default();
+            warn!(
+                "Unable to infer dialect of {}, {:?} is used. Pass --dialect to set it.",
+                path_to_string(path),
+                dialect
+            );
+            dialect
+        }
+    }
+}
+
+fn list_inputs(opts: &cli::InputFs) -> Result<Vec<PathBuf>> {
+    let path = opts.input_path();
+    let mut inputs: Vec<PathBuf> = if path.is_dir() {
+        std::fs::read_dir(path)?
+            .filter_map(|e| e.ok())
+            .map(|e| e.path())
+            .filter(|p| p.is_file() && p.extension().map(|ext| ext == "mv").unwrap_or_default())
+            .collect()
+    } else if path.is_file() {
+        vec![path.to_owned()]
+    } else {
+        glob::glob(&path_to_string(path))?
+            .filter_map(|p| p.ok())
+            .filter(|p| p.is_file())
+            .collect()
+    };
+    inputs.sort();
+
+    if inputs.is_empty() {
+        bail!("No input bytecode found by {}", path_to_string(path));
+    }
+    debug!("inputs: ({}) {:?}", inputs.len(), inputs);
+    Ok(inputs)
+}
+
+fn analyse_input(
+    opts: &cli::Opts,
+    input_type: MoveType,
+    input: CompiledMove,
+    dialect: Dialect,
+    deps: Arc<ModMap>,
+    missed_deps: &UnresolvedMap<ModAddr>,
+    upgrade: Option<UpgradeDiff>,
+    source_map: Option<MoveSourceMap>,
+) -> Db {
+    let detected_input_type = input.extract();
     if input_type != detected_input_type {
         warn!(
             "Requested source type ({:?}) is not equal detected ({:?}).",
@@ -101,11 +431,15 @@ fn run(opts: cli::Opts) {
         );
     }
 
+    // modules of the batch are resolved along with the dependencies, the root is skipped:
+    let root_addr: ModAddr = input.extract();
+    let dep_modules = || deps.iter().filter(|(addr, _)| **addr != root_addr);
+
     // extract structs, resources
     let struct_map: StructMap = {
-        let deps_iter = deps
-            .iter()
-            .map(|(_, dep)| extract_struct_map(dep.bytecode()).into_iter())
+        let deps_iter = dep_modules()
+            .filter_map(|(_, dep)| dep.bytecode())
+            .map(|bc| extract_struct_map(bc).into_iter())
             .flatten();
         if let CompiledMove::Module(compiled_mod) = &input {
             extract_struct_map(&compiled_mod)
@@ -119,17 +453,17 @@ fn run(opts: cli::Opts) {
 
     // extract functions
     let fn_map: FnMap = {
-        let deps_iter = deps
-            .iter()
-            .map(|(_, dep)| extract_functions(dep.bytecode()).into_iter())
+        let deps_iter = dep_modules()
+            .filter_map(|(_, dep)| dep.bytecode())
+            .map(|bc| extract_functions(bc, true).into_iter())
             .flatten();
         match &input {
-            CompiledMove::Module(bc) => extract_functions(&bc)
+            CompiledMove::Module(bc) => extract_functions(&bc, true)
                 .into_iter()
                 .chain(deps_iter)
                 .collect(),
 
-            CompiledMove::Script(bc) => extract_script_main_fn(&bc)
+            CompiledMove::Script(bc) => extract_script_main_fn(&bc, source_map.as_ref())
                 .into_iter()
                 .chain(deps_iter)
                 .collect(),
@@ -138,10 +472,9 @@ fn run(opts: cli::Opts) {
 
     // get entry point(s)
     let entry_points: Vec<FnAddr> = {
-        let root_mod: ModAddr = input.extract();
         fn_map
             .iter()
-            .filter(|(k, v)| k.addr() == &root_mod && v.is_public)
+            .filter(|(k, v)| k.addr() == &root_addr && v.is_public)
             .map(|(k, _)| k)
             .cloned()
             .collect()
@@ -155,8 +488,50 @@ fn run(opts: cli::Opts) {
         debug!("entry point: {:x}", ep);
     }
 
-    let mut db = Db {
-        dialect: opts.input.dialect,
+    // decode constant pools
+    let constants: ConstMap = {
+        let root = extract_constants(&input.as_ref(), dialect);
+        dep_modules()
+            .filter_map(|(addr, dep)| dep.bytecode().map(|bc| (addr, bc)))
+            .map(|(addr, bc)| {
+                let bc = CompiledMoveRef::from(bc);
+                (addr.to_owned(), extract_constants(&bc, dialect))
+            })
+            .chain(std::iter::once((root_addr.to_owned(), root)))
+            .collect()
+    };
+
+    let call_graph = analyse::call_graph::CallGraph::new(&fn_map);
+    let cfg = analyse::cfg::build_cfg_map(&fn_map, &constants);
+
+    // run the verifier on the input and resolved dependencies
+    let findings: Vec<_> = {
+        let root = match &input {
+            CompiledMove::Module(bc) => analyse::verify::verify_module(bc, &deps),
+            CompiledMove::Script(bc) => analyse::verify::verify_script(bc, &deps),
+        };
+        let mut deps_findings: Vec<_> = dep_modules()
+            .filter_map(|(_, dep)| dep.bytecode())
+            .flat_map(|bc| analyse::verify::verify_module(bc, &deps))
+            .collect();
+        deps_findings.sort_by_key(|f| format!("{:#x}", f.module));
+        root.into_iter().chain(deps_findings).collect()
+    };
+    for finding in &findings {
+        warn!(
+            "{:#x} is rejected by the verifier: {}",
+            finding.module, finding.status
+        );
+    }
+
+    let sources = if opts.output.decompile {
+        decompile_sources(&input, &deps, dialect)
+    } else {
+        Default::default()
+    };
+
+    Db {
+        dialect,
         root: DbRoot {
             bc: input,
             kind: detected_input_type,
@@ -165,34 +540,97 @@ fn run(opts: cli::Opts) {
         modules: deps,
         functions: fn_map,
         structs: struct_map,
+        call_graph,
+        cfg,
+        constants,
+        sources,
+        findings,
+        upgrade,
         missed_modules: missed_deps.iter().map(|(addr, _)| addr).cloned().collect(),
-    };
-
-    // TODO: filter out unused entries
+        hidden_modules: Default::default(),
+    }
+}
 
+fn render(output: &cli::Output, db: Db) {
     // TODO: analyze
 
-    // render
-    let ctx = db.into_context();
-    output::tmt::render(&opts.output, ctx)
-        .map_err(|err| error!("{}", err))
-        .ok();
+    let dialect = db.dialect;
+    let mut ctx = db.into_context();
+    let mut assets = ctx.source_files();
+    if !output.inline {
+        assets.extend(ctx.take_diagram_files());
+    }
+    if let Err(err) = output::tmt::render(output, dialect, ctx, assets) {
+        error!("{}", err);
+        std::process::exit(1);
+    }
+}
+
+fn decompile_sources(root: &CompiledMove, deps: &ModMap, dialect: Dialect) -> Vec<SourceEntry> {
+    let root_addr: ModAddr = root.extract();
+    let root_file = format!("{}.move", root.name_str());
+    let mut deps: Vec<_> = deps
+        .values()
+        .filter(|dep| dep.mod_addr() != root_addr)
+        .filter_map(|dep| {
+            let file = format!("deps/{:#x}/{}.move", dep.address(), dep.name());
+            let bc = CompiledMoveRef::from(dep.bytecode()?);
+            Some((dep.mod_addr(), file, bc))
+        })
+        .collect();
+    deps.sort_by(|(_, a, _), (_, b, _)| a.cmp(b));
+
+    std::iter::once((root_addr, root_file, root.as_ref()))
+        .chain(deps)
+        .filter_map(
+            |(address, file, bc)| match decompiler::decompile(bc, dialect) {
+                Ok(code) => Some(SourceEntry {
+                    address,
+                    file,
+                    code,
+                }),
+                Err(err) => {
+                    warn!("unable to decompile {:#x}: {}", address, err);
+                    None
+                }
+            },
+        )
+        .collect()
 }
 
-fn read_deps(opts: &cli::Input, input_deps: &[ModAddr]) -> (ModMap, UnresolvedMap<ModAddr>) {
-    let deps_local = read_offline_deps(&opts);
+fn read_deps(
+    opts: &cli::Input,
+    siblings: Vec<(PathBuf, CompiledModule)>,
+    input_deps: &[ModAddr],
+) -> (ModMap, UnresolvedMap<ModAddr>) {
+    let mut deps_local = read_offline_deps(&opts);
+    for (path, bc) in siblings {
+        deps_local.insert_mod(path, bc);
+    }
+    let cache = opts.online.cache().map(Arc::new);
 
     // create online deps-resolver(s), resolve all deps in DependencyMap recursively, then destroy.
-    let (deps, missed_deps) = if !opts.online.offline {
-        use deps::online::*;
-        let searchers = opts
-            .online
-            .ds
-            .iter()
-            .cloned()
-            .map(deps::online::OnlineDependencySearch::new);
-        let mut resolver = deps::resolver::DependencyResolverMap::new(deps_local);
-        searchers.for_each(|s| resolver.add_searcher(s));
+    let (deps, missed_deps) = if !opts.online.ds.is_empty() {
+        use deps::cache::CacheDependencySearch;
+        use deps::online::OnlineDependencySearch;
+
+        let mut resolver =
+            deps::resolver::DependencyResolverMap::new(deps_local).with_jobs(opts.online.jobs());
+        // cached modules go first unless they should be fetched again:
+        if let Some(cache) = cache.as_ref().filter(|_| !opts.online.refresh_cache) {
+            for uri in &opts.online.ds {
+                resolver.add_searcher(CacheDependencySearch::new(cache.clone(), uri.to_owned()));
+            }
+        }
+        if !opts.online.offline {
+            for uri in &opts.online.ds {
+                let searcher = OnlineDependencySearch::new(uri.to_owned());
+                resolver.add_searcher(match &cache {
+                    Some(cache) => searcher.with_cache(cache.clone()),
+                    None => searcher,
+                });
+            }
+        }
         resolver.prefetch_deps(&input_deps);
         resolver.prefetch_deps_recursively();
         resolver.split()
@@ -207,9 +645,37 @@ fn read_deps(opts: &cli::Input, input_deps: &[ModAddr]) -> (ModMap, UnresolvedMa
     (deps, missed_deps)
 }
 
-fn read_input(opts: &cli::Opts) -> (MoveType, CompiledMove, Vec<ModAddr>) {
-    let mut bytes = std::fs::read(&opts.input.offline.path).expect("Unable to read input bytecode");
-    compat::adapt(&mut bytes).expect("Valid bytecode.");
+fn read_source_map(opts: &cli::Opts) -> Option<MoveSourceMap> {
+    let path = opts.input.offline.source_map.as_ref()?;
+    match libra::bytecode_source_map::utils::source_map_from_file(path) {
+        Ok(map) => Some(map),
+        Err(err) => {
+            warn!(
+                "Unable to read source map {}: {}",
+                path_to_string(path),
+                err
+            );
+            None
+        }
+    }
+}
+
+fn read_input(
+    opts: &cli::Opts,
+    path: &Path,
+) -> Result<
+    (
+        MoveType,
+        CompiledMove,
+        Vec<ModAddr>,
+        Option<InferredDialect>,
+    ),
+    InputError,
+> {
+    let mut bytes = std::fs::read(path).map_err(|err| InputError::Io(path.to_owned(), err))?;
+    let raw = bytes.clone();
+    let width = compat::adapt_to(&mut bytes, AddressWidth::Dfinance).map_err(InputError::Adapt)?;
+    debug!("input addresses width: {:?}", width);
 
     let source_type = match opts.input.offline.kind {
         InputType::Script => MoveType::Script,
@@ -225,25 +691,38 @@ fn read_input(opts: &cli::Opts) -> (MoveType, CompiledMove, Vec<ModAddr>) {
         }
     };
 
-    let root =
-        disasm::CompiledMove::deserialize(&bytes).expect("Input bytecode can't be deserialized");
+    let root = disasm::CompiledMove::deserialize(&bytes)?;
     let root_deps = extract_mod_handles(&root);
 
     #[rustfmt::skip]
 	debug!("input.deps: ({}) [{}]", root_deps.len(), root_deps.iter().map(|m| format!("{:#x}", m)).collect::<Vec<_>>().join(", "));
 
-    (source_type, root, root_deps)
+    let dialect = analyse::dialect::infer_dialect(&raw, &root);
+
+    Ok((source_type, root, root_deps, dialect))
+}
+
+/// Reads the previous version of the input module if requested.
+fn read_previous_version(opts: &cli::Opts) -> Result<Option<CompiledModule>> {
+    let path = match &opts.input.offline.upgrade_from {
+        Some(path) => path,
+        None => return Ok(None),
+    };
+    let mut bytes = std::fs::read(path).map_err(|err| InputError::Io(path.to_owned(), err))?;
+    compat::adapt_to(&mut bytes, AddressWidth::Dfinance).map_err(InputError::Adapt)?;
+    let module = CompiledModule::deserialize(&bytes).map_err(InputError::from)?;
+    Ok(Some(module))
 }
 
 fn read_offline_deps(opts: &cli::Input) -> ModMap {
     let mut index = ModMap::default();
     let deps = deps::offline::OfflineDependencySearch::new_from_opts(&opts.offline);
     deps.into_load_all().for_each(|(k, v)| match v {
-        Ok(mut bytes) => {
-            compat::adapt(&mut bytes).expect("Valid bytecode.");
-            index.insert_file(k, bytes)
-        }
-        Err(err) => error!("Unable to load {} : {}", path_to_string(&k), err),
+        Ok(mut bytes) => match compat::adapt_to(&mut bytes, AddressWidth::Dfinance) {
+            Ok(_) => index.insert_file(k, bytes),
+            Err(err) => index.insert_broken(k, None, InputError::Adapt(err)),
+        },
+        Err(err) => index.insert_broken(k.clone(), None, InputError::Io(k, err)),
     });
     //
     // here can add some more
@@ -252,3 +731,79 @@ fn read_offline_deps(opts: &cli::Input) -> ModMap {
     index.build_deps_links();
     index
 }
+
+#[cfg(test)]
+mod tests {
+    use clap::{FromArgMatches, IntoApp};
+    use super::*;
+
+    #[test]
+    fn batch_of_directory() {
+        let dir = fixtures::temp_dir("batch");
+        let input = dir.join("input");
+        let out = dir.join("out");
+        std::fs::create_dir_all(&input).unwrap();
+        for name in &["0_Debug", "5_Vector", "27_Option"] {
+            let bytes = fixtures::raw(&format!("modules/{}", name));
+            std::fs::write(input.join(format!("{}.mv", name)), bytes).unwrap();
+        }
+        std::fs::write(input.join("broken.mv"), b"not a module").unwrap();
+        std::fs::write(input.join("notes.txt"), b"skipped").unwrap();
+
+        let args = vec![
+            "move-unscrambler".to_owned(),
+            "-i".to_owned(),
+            path_to_string(&input),
+            "-o".to_owned(),
+            path_to_string(&out),
+            "--fmt".to_owned(),
+            "json".to_owned(),
+            "--no-cache".to_owned(),
+        ];
+        let matches = cli::Opts::into_app().get_matches_from(args);
+        let opts = validate_config(cli::Opts::from_arg_matches(&matches)).unwrap();
+        run(opts);
+
+        let index: serde_json::Value =
+            serde_json::from_str(&std::fs::read_to_string(out.join("index.json")).unwrap())
+                .unwrap();
+        let reports = index["reports"].as_array().unwrap();
+        let inputs: Vec<_> = reports
+            .iter()
+            .map(|r| r["input"].as_str().unwrap().to_owned())
+            .collect();
+        assert_eq!(inputs.len(), 4, "{:?}", inputs);
+        assert!(inputs[0].ends_with("0_Debug.mv"), "{:?}", inputs);
+        assert!(inputs.iter().all(|i| !i.ends_with("notes.txt")));
+
+        // unreadable input is listed without a report:
+        let broken = reports
+            .iter()
+            .find(|r| r["input"].as_str().unwrap().ends_with("broken.mv"))
+            .unwrap();
+        assert!(broken["error"].is_string());
+        assert!(broken["report"].is_null());
+
+        for name in &["0_Debug", "5_Vector", "27_Option"] {
+            let entry = reports
+                .iter()
+                .find(|r| {
+                    r["input"]
+                        .as_str()
+                        .unwrap()
+                        .ends_with(&format!("{}.mv", name))
+                })
+                .unwrap();
+            assert!(entry["error"].is_null());
+            let report = out.join(entry["report"].as_str().unwrap());
+            assert_eq!(report, out.join(name).join("output.json"));
+            assert!(report.is_file(), "{}", path_to_string(&report));
+        }
+
+        // sibling module of the batch is the resolved dependency:
+        let option = std::fs::read_to_string(out.join("27_Option").join("output.json")).unwrap();
+        assert!(option.contains("::Vector::is_empty\""), "{}", option);
+
+        std::fs::remove_dir_all(&dir).ok();
+    }
+}
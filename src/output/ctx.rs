@@ -4,15 +4,62 @@ use crate::cli::Dialect;
 use crate::extract::prelude::*;
 use crate::disasm::CompiledMove;
 use crate::{
+    analyse::{
+        call_graph::CallGraph,
+        cfg::{CfgMap, FnCfg},
+        upgrade::UpgradeDiff,
+        verify::Finding,
+        effects::effects_transitive,
+    },
     data::{DbRoot, Db},
     deps::map::ModMap,
 };
 use std::collections::HashMap;
+use super::tmt::FilesMap;
+use super::diagram::{render_dot, render_mermaid};
 
 #[derive(Debug, Serialize)]
 pub struct Ctx<Si> {
     root: Root,
     dependencies: Dependencies<Si>,
+    /// Constant pools, the input goes first.
+    constants: Vec<ConstPoolEntry>,
+    /// Errors reported by the bytecode verifier, the input goes first.
+    verification: Vec<Finding>,
+    /// Compatibility with the previous version of the input module.
+    upgrade: Option<UpgradeDiff>,
+    /// Decompiled sources, the input goes first.
+    sources: Vec<SourceEntry>,
+}
+
+impl<Si> Ctx<Si> {
+    /// Decompiled sources as files to save with the report.
+    pub fn source_files(&self) -> FilesMap {
+        self.sources
+            .iter()
+            .map(|s| (s.file.to_owned(), s.code.to_owned()))
+            .collect()
+    }
+
+    /// Moves control-flow diagrams out of the report into files to save next to it.
+    pub fn take_diagram_files(&mut self) -> FilesMap {
+        let mut files = FilesMap::new();
+        let functions = self
+            .root
+            .entry_points
+            .iter_mut()
+            .map(|ep| &mut ep.function)
+            .chain(self.dependencies.functions.values_mut());
+        for cfg in functions.filter_map(|f| f.cfg.as_mut()) {
+            if let Some(dot) = cfg.dot.take() {
+                files.insert(cfg.dot_file.to_owned(), dot);
+            }
+            if let Some(mermaid) = cfg.mermaid.take() {
+                files.insert(cfg.mermaid_file.to_owned(), mermaid);
+            }
+        }
+        files
+    }
 }
 
 /// Contains user's input
@@ -27,19 +74,113 @@ pub struct Root {
 pub struct EntryPoint {
     pub address: FnAddr,
     function: FnKnowledgeBasic,
+    /// All functions reachable from the entry point.
+    calls_transitive: Vec<FnAddr>,
+    /// Global storage touched by the entry point and all functions it reaches.
+    storage: StorageEffects,
 }
 
 /// Contains user's input
 #[derive(Debug, Serialize)]
 pub struct Dependencies<Si> {
     functions: FnKnowledgeMap<FnKnowledgeBasic>,
-    structs: StructKnowledgeMap<Si>,
+    structs: StructEntryMap<Si>,
+    /// Dependencies which can't be loaded.
+    broken: Vec<BrokenEntry>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct BrokenEntry {
+    /// Address the module was requested by, unknown for files.
+    pub module: Option<ModAddr>,
+    pub source: String,
+    pub reason: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ConstPoolEntry {
+    pub address: ModAddr,
+    pub constants: Vec<ConstInfo>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct SourceEntry {
+    pub address: ModAddr,
+    /// Path of the file relative to the output directory.
+    pub file: String,
+    pub code: String,
+}
+
+/// Index page of the batch.
+#[derive(Debug, Clone, Serialize)]
+pub struct IndexCtx {
+    pub reports: Vec<IndexEntry>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct IndexEntry {
+    /// Path of the input bytecode-file.
+    pub input: String,
+    /// `None` if the input can't be read.
+    pub address: Option<ModAddr>,
+    pub is_script: bool,
+    /// Path of the report relative to the index page, `None` if the input can't be read.
+    pub report: Option<String>,
+    /// The input is rejected by the bytecode verifier.
+    pub rejected: bool,
+    /// Reason the input can't be read.
+    pub error: Option<String>,
+}
+
+/// Control-flow graph of the function rendered as diagrams.
+#[derive(Debug, Clone, Eq, PartialEq, Hash, Serialize)]
+pub struct CfgDiagram {
+    /// Graphviz DOT source, `None` if saved to `dot_file`.
+    pub dot: Option<String>,
+    pub dot_file: String,
+    /// Mermaid source, `None` if saved to `mermaid_file`.
+    pub mermaid: Option<String>,
+    pub mermaid_file: String,
+}
+
+impl CfgDiagram {
+    pub fn new(cfg: &FnCfg) -> Self {
+        let (module, name) = cfg.address.split_ref();
+        let file = format!("cfg/{:#x}/{}/{}", module.addr(), module.name(), name);
+        Self {
+            dot: Some(render_dot(cfg)),
+            dot_file: format!("{}.dot", file),
+            mermaid: Some(render_mermaid(cfg)),
+            mermaid_file: format!("{}.mmd", file),
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct StructEntry<Si> {
+    pub address: StructAddr,
+    #[serde(flatten)]
+    pub info: Si,
 }
 
 /// Storage for final results
-pub trait Context<Si>: ExtractRef<FnMap> + Extract<StructKnowledgeMap<Si>> {
+pub trait Context<Si>:
+    ExtractRef<FnMap>
+    + ExtractRef<ModMap>
+    + ExtractRef<CallGraph>
+    + ExtractRef<CfgMap>
+    + ExtractRef<ConstMap>
+    + ExtractRef<[SourceEntry]>
+    + ExtractRef<[Finding]>
+    + ExtractRef<Option<UpgradeDiff>>
+    + Extract<StructKnowledgeMap<Si>>
+{
     type Root: ContextRoot;
     fn root(&self) -> &Self::Root;
+    /// Broken dependency `module` is listed in the report.
+    fn is_reported(&self, _module: &ModAddr) -> bool {
+        true
+    }
 }
 
 pub trait ContextRoot: ExtractRef<CompiledMove> + Extract<MoveType> {
@@ -50,6 +191,7 @@ pub trait ContextFnKnowledge {}
 
 type FnKnowledgeMap<Fi> = HashMap<String /* FnAddr */, Fi>;
 type StructKnowledgeMap<Si> = HashMap<StructAddr, Si>;
+type StructEntryMap<Si> = HashMap<String /* StructAddr */, StructEntry<Si>>;
 
 pub trait StructKnowledge: Extract<StructKind> + ExtractRef<[TypeParamKind]> {
     fn is_native(&self) -> bool;
@@ -73,13 +215,19 @@ pub trait FnKnowledge: ExtractRef<[TypeParamKind]> {
 
     fn acquires(&self) -> &[StructAddr];
 
+    fn calls(&self) -> &[FnAddr];
+    fn callers(&self) -> &[FnAddr];
+
     fn type_params(&self) -> &[TypeParamKind] {
         self.extract_ref()
     }
 
     fn parameters(&self) -> &[Ty];
+    fn parameter_names(&self) -> &[String];
     fn returns(&self) -> &[Ty];
 
+    fn aborts(&self) -> &[AbortInfo];
+
     // TODO: fn code(&self) -> something serializable;
 }
 
@@ -89,9 +237,15 @@ pub struct FnKnowledgeBasic {
     pub is_public: bool,
     pub is_native: bool,
     pub parameters: Vec<Ty>,
+    /// Names of parameters, `argN` if unknown.
+    pub parameter_names: Vec<String>,
     pub type_parameters: Vec<TypeParamKind>,
     pub returns: Vec<Ty>,
     pub acquires: Vec<StructAddr>,
+    pub calls: Vec<FnAddr>,
+    pub callers: Vec<FnAddr>,
+    pub cfg: Option<CfgDiagram>,
+    pub aborts: Vec<AbortInfo>,
     // pub code: Option<CodeUnit>,
 }
 
@@ -117,6 +271,13 @@ impl FnKnowledge for FnKnowledgeBasic {
         &self.acquires
     }
 
+    fn calls(&self) -> &[FnAddr] {
+        &self.calls
+    }
+    fn callers(&self) -> &[FnAddr] {
+        &self.callers
+    }
+
     fn type_params(&self) -> &[TypeParamKind] {
         self.extract_ref()
     }
@@ -124,10 +285,17 @@ impl FnKnowledge for FnKnowledgeBasic {
     fn parameters(&self) -> &[Ty] {
         &self.parameters
     }
+    fn parameter_names(&self) -> &[String] {
+        &self.parameter_names
+    }
     fn returns(&self) -> &[Ty] {
         &self.returns
     }
 
+    fn aborts(&self) -> &[AbortInfo] {
+        &self.aborts
+    }
+
     // TODO: fn code(&self) -> something serializable;
 }
 
@@ -145,10 +313,45 @@ where
 
         // XXX: opt
         let mut strct_map: StructKnowledgeMap<Si> = self.extract();
-        let mut fn_map: FnKnowledgeMap<FnKnowledgeBasic> = self
-            .extract_ref()
-            .into_iter()
-            .map(|(k, v)| (format!("{:#x}", k), (k, v).extract()))
+        let functions: &FnMap = self.extract_ref();
+        let call_graph: &CallGraph = self.extract_ref();
+        let cfg_map: &CfgMap = self.extract_ref();
+        let sources: &[SourceEntry] = self.extract_ref();
+        let findings: &[Finding] = self.extract_ref();
+        let upgrade: &Option<UpgradeDiff> = self.extract_ref();
+        let modules: &ModMap = self.extract_ref();
+        let const_map: &ConstMap = self.extract_ref();
+        let root_addr: ModAddr = root_bc.extract();
+        let mut constants: Vec<_> = const_map
+            .iter()
+            .filter(|(_, pool)| !pool.is_empty())
+            .map(|(address, pool)| ConstPoolEntry {
+                address: address.to_owned(),
+                constants: pool.to_owned(),
+            })
+            .collect();
+        constants
+            .sort_by_key(|entry| (entry.address != root_addr, format!("{:#x}", entry.address)));
+        let mut broken: Vec<_> = modules
+            .iter()
+            .filter(|(addr, _)| self.is_reported(addr))
+            .filter_map(|(_, info)| {
+                info.error().map(|err| BrokenEntry {
+                    module: info.requested().cloned(),
+                    source: info.source().to_string(),
+                    reason: err.to_string(),
+                })
+            })
+            .collect();
+        broken.sort_by(|a, b| a.source.cmp(&b.source));
+        let mut fn_map: FnKnowledgeMap<FnKnowledgeBasic> = functions
+            .iter()
+            .map(|(k, v)| {
+                let mut f: FnKnowledgeBasic = (k, v).extract();
+                f.callers = call_graph.callers(k).to_vec();
+                f.cfg = cfg_map.get(k).map(CfgDiagram::new);
+                (format!("{:#x}", k), f)
+            })
             .collect();
 
         Ctx {
@@ -163,20 +366,33 @@ where
                         fn_map.get(&format!("{:#x}", addr)).map(|f| EntryPoint {
                             address: addr.to_owned(),
                             function: f.to_owned(),
+                            calls_transitive: call_graph.callees_transitive(addr),
+                            storage: effects_transitive(functions, call_graph, addr),
                         })
                     })
                     .collect(),
             },
             dependencies: Dependencies {
-                structs: {
-                    strct_map.drain().all(|_| true);
-                    strct_map
-                },
+                structs: strct_map
+                    .drain()
+                    .map(|(k, v)| {
+                        let entry = StructEntry {
+                            address: k,
+                            info: v,
+                        };
+                        (format!("{:#x}", entry.address), entry)
+                    })
+                    .collect(),
                 functions: {
                     // fn_map.drain().all(|_| true);
                     fn_map
                 },
+                broken,
             },
+            constants,
+            verification: findings.to_vec(),
+            upgrade: upgrade.to_owned(),
+            sources: sources.to_vec(),
         }
     }
 }
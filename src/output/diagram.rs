@@ -0,0 +1,115 @@
+use std::fmt::Write;
+use crate::analyse::cfg::{EdgeKind, FnCfg};
+
+/// Renders the control-flow graph as Graphviz DOT.
+pub fn render_dot(cfg: &FnCfg) -> String {
+    let mut out = String::new();
+    writeln!(
+        out,
+        "digraph \"{}\" {{",
+        escape_dot(&format!("{:#x}", cfg.address))
+    )
+    .ok();
+    writeln!(out, "\tnode [shape=box, fontname=monospace];").ok();
+    for block in &cfg.blocks {
+        let mut label = format!("#{}\\l", block.start);
+        for op in &block.code {
+            label.push_str(&escape_dot(op));
+            label.push_str("\\l");
+        }
+        writeln!(out, "\tb{} [label=\"{}\"];", block.start, label).ok();
+        for edge in &block.edges {
+            match edge_label(edge.kind) {
+                Some(l) => writeln!(
+                    out,
+                    "\tb{} -> b{} [label=\"{}\"];",
+                    block.start,
+                    edge.target.id(),
+                    l
+                ),
+                None => writeln!(out, "\tb{} -> b{};", block.start, edge.target.id()),
+            }
+            .ok();
+        }
+    }
+    out.push_str("}\n");
+    out
+}
+
+/// Renders the control-flow graph as Mermaid flowchart.
+pub fn render_mermaid(cfg: &FnCfg) -> String {
+    let mut out = String::from("graph TD\n");
+    for block in &cfg.blocks {
+        let mut label = format!("#{}", block.start);
+        for op in &block.code {
+            label.push_str("<br/>");
+            label.push_str(&escape_mermaid(op));
+        }
+        writeln!(out, "\tb{}[\"{}\"]", block.start, label).ok();
+        for edge in &block.edges {
+            match edge_label(edge.kind) {
+                Some(l) => writeln!(out, "\tb{} -->|{}| b{}", block.start, l, edge.target.id()),
+                None => writeln!(out, "\tb{} --> b{}", block.start, edge.target.id()),
+            }
+            .ok();
+        }
+    }
+    out
+}
+
+fn edge_label(kind: EdgeKind) -> Option<&'static str> {
+    match kind {
+        EdgeKind::True => Some("true"),
+        EdgeKind::False => Some("false"),
+        EdgeKind::Jump | EdgeKind::Fallthrough => None,
+    }
+}
+
+fn escape_dot(s: &str) -> String {
+    s.replace('\\', "\\\\").replace('"', "\\\"")
+}
+
+fn escape_mermaid(s: &str) -> String {
+    s.replace('"', "#quot;")
+}
+
+#[cfg(test)]
+mod tests {
+    use libra::vm::file_format::{Bytecode::*, CodeUnit, SignatureIndex};
+    use crate::types::{FnAddr, ModAddr};
+    use super::*;
+
+    fn branchy_cfg() -> FnCfg {
+        let code = CodeUnit {
+            locals: SignatureIndex::new(0),
+            code: vec![CopyLoc(0), BrFalse(3), Ret, Ret],
+        };
+        FnCfg::new(&FnAddr::new(ModAddr::test_addr_42(), "foo"), &code, &[])
+    }
+
+    #[test]
+    fn dot() {
+        let dot = render_dot(&branchy_cfg());
+        assert!(dot.starts_with("digraph \""));
+        assert!(dot.ends_with("}\n"));
+        assert!(dot.contains("\tb0 [label=\"#0\\lCopyLoc(0)\\lBrFalse(3)\\l\"];\n"));
+        assert!(dot.contains("\tb0 -> b2 [label=\"true\"];\n"));
+        assert!(dot.contains("\tb0 -> b3 [label=\"false\"];\n"));
+        assert!(dot.contains("\tb3 [label=\"#3\\lRet\\l\"];\n"));
+    }
+
+    #[test]
+    fn mermaid() {
+        let mermaid = render_mermaid(&branchy_cfg());
+        assert!(mermaid.starts_with("graph TD\n"));
+        assert!(mermaid.contains("\tb0[\"#0<br/>CopyLoc(0)<br/>BrFalse(3)\"]\n"));
+        assert!(mermaid.contains("\tb0 -->|true| b2\n"));
+        assert!(mermaid.contains("\tb0 -->|false| b3\n"));
+    }
+
+    #[test]
+    fn escape() {
+        assert_eq!("a\\\"b\\\\", escape_dot("a\"b\\"));
+        assert_eq!("#quot;x#quot;", escape_mermaid("\"x\""));
+    }
+}
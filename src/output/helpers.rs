@@ -0,0 +1,200 @@
+use handlebars::{
+    Context, Handlebars, Helper, HelperResult, Output, RenderContext, RenderError,
+    handlebars_helper,
+};
+use serde_json::Value;
+use crate::cli::Dialect;
+use crate::types::{display_address, parse_address};
+
+/// Registers helpers available in all report templates,
+/// `addr`, `addr_full` and `ty` format addresses as it's usual for the `dialect`.
+pub fn register(hb: &mut Handlebars, dialect: Dialect) {
+    handlebars_helper!(hex: |v: i64| format!("0x{:x}", v));
+    handlebars_helper!(addr_hex: |v: Json| fmt_addr(v));
+    handlebars_helper!(anchor: |kind: str, v: Json| format!("{}-{}", kind, anchor_id(v)));
+
+    hb.register_helper("hex", Box::new(hex));
+    hb.register_helper("addr_hex", Box::new(addr_hex));
+    hb.register_helper(
+        "addr",
+        Box::new(AddrHelper {
+            dialect,
+            full: false,
+        }),
+    );
+    hb.register_helper(
+        "addr_full",
+        Box::new(AddrHelper {
+            dialect,
+            full: true,
+        }),
+    );
+    hb.register_helper("anchor", Box::new(anchor));
+    hb.register_helper("ty", Box::new(TyHelper { dialect }));
+}
+
+fn flatten(v: &Value, parts: &mut Vec<String>) {
+    match v {
+        Value::Array(items) => items.iter().for_each(|v| flatten(v, parts)),
+        Value::String(s) => parts.push(s.to_owned()),
+        Value::Null => {}
+        other => parts.push(other.to_string()),
+    }
+}
+
+/// Formats serialized `ModAddr`, `FnAddr` or `StructAddr` as `0xADDR::Mod[::name]`.
+pub fn fmt_addr(v: &Value) -> String {
+    let mut parts = Vec::new();
+    flatten(v, &mut parts);
+    if let Some(first) = parts.first_mut() {
+        if !first.starts_with("0x") {
+            first.insert_str(0, "0x");
+        }
+    }
+    parts.join("::")
+}
+
+/// Formats serialized address in the dialect,
+/// with `full` the hex form of Dfinance wallet addresses follows in parentheses.
+struct AddrHelper {
+    dialect: Dialect,
+    full: bool,
+}
+
+impl handlebars::HelperDef for AddrHelper {
+    fn call<'reg: 'rc, 'rc>(
+        &self,
+        h: &Helper<'reg, 'rc>,
+        _: &'reg Handlebars<'reg>,
+        _: &'rc Context,
+        _: &mut RenderContext<'reg, 'rc>,
+        out: &mut dyn Output,
+    ) -> HelperResult {
+        let v = h
+            .param(0)
+            .ok_or_else(|| RenderError::new("Address is required"))?
+            .value();
+        let hex = fmt_addr(v);
+        let text = fmt_addr_dialect(v, self.dialect);
+        if self.full && self.dialect == Dialect::Dfinance && text != hex {
+            out.write(&format!("{} ({})", text, hex))?;
+        } else {
+            out.write(&text)?;
+        }
+        Ok(())
+    }
+}
+
+/// Formats serialized address like `fmt_addr` but the account address is in the `dialect` form.
+pub fn fmt_addr_dialect(v: &Value, dialect: Dialect) -> String {
+    let mut parts = Vec::new();
+    flatten(v, &mut parts);
+    if let Some(first) = parts.first_mut() {
+        match parse_address(first) {
+            Ok(addr) => *first = display_address(&addr, dialect),
+            Err(_) => return fmt_addr(v),
+        }
+    }
+    parts.join("::")
+}
+
+/// Formats serialized type in Move syntax, owners of structs are in the dialect form.
+struct TyHelper {
+    dialect: Dialect,
+}
+
+impl handlebars::HelperDef for TyHelper {
+    fn call<'reg: 'rc, 'rc>(
+        &self,
+        h: &Helper<'reg, 'rc>,
+        _: &'reg Handlebars<'reg>,
+        _: &'rc Context,
+        _: &mut RenderContext<'reg, 'rc>,
+        out: &mut dyn Output,
+    ) -> HelperResult {
+        let v = h
+            .param(0)
+            .ok_or_else(|| RenderError::new("Type is required"))?
+            .value();
+        out.write(&fmt_ty(v, self.dialect))?;
+        Ok(())
+    }
+}
+
+/// Produces html-id-safe identifier for serialized address.
+pub fn anchor_id(v: &Value) -> String {
+    let mut parts = Vec::new();
+    flatten(v, &mut parts);
+    parts.join("-").to_lowercase()
+}
+
+/// Formats serialized `Ty` in Move syntax with addresses in the `dialect` form.
+pub fn fmt_ty(v: &Value, dialect: Dialect) -> String {
+    match v {
+        Value::String(s) => match s.as_str() {
+            "Bool" => "bool",
+            "U8" => "u8",
+            "U64" => "u64",
+            "U128" => "u128",
+            "Address" => "address",
+            "Signer" => "signer",
+            other => other,
+        }
+        .to_owned(),
+        Value::Object(map) => map
+            .iter()
+            .next()
+            .map(|(kind, inner)| match kind.as_str() {
+                "Vector" => format!("vector<{}>", fmt_ty(inner, dialect)),
+                "Reference" => format!("&{}", fmt_ty(inner, dialect)),
+                "MutableReference" => format!("&mut {}", fmt_ty(inner, dialect)),
+                "Struct" => fmt_addr_dialect(inner, dialect),
+                "StructInstantiation" => {
+                    let addr = inner
+                        .get(0)
+                        .map(|v| fmt_addr_dialect(v, dialect))
+                        .unwrap_or_default();
+                    let ty_args: Vec<_> = inner
+                        .get(1)
+                        .and_then(Value::as_array)
+                        .map(|tys| tys.iter().map(|ty| fmt_ty(ty, dialect)).collect())
+                        .unwrap_or_default();
+                    format!("{}<{}>", addr, ty_args.join(", "))
+                }
+                "TypeParameter" => format!("T{}", inner),
+                other => other.to_owned(),
+            })
+            .unwrap_or_default(),
+        _ => String::new(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use libra::libra_types::account_address::AccountAddress;
+    use crate::types::{display_address, ModAddr, StructAddr, Ty};
+    use super::*;
+
+    #[test]
+    fn ty_in_dialect() {
+        let addr = AccountAddress::new([0x2a; AccountAddress::LENGTH]);
+        let coin = StructAddr::new(ModAddr::new(addr, "Coins"), "Coin");
+        let ty = Ty::Reference(Box::new(Ty::StructInstantiation(
+            coin.to_owned(),
+            vec![Ty::Vector(Box::new(Ty::Struct(coin))), Ty::U64],
+        )));
+        let v = serde_json::to_value(&ty).unwrap();
+
+        let hex = display_address(&addr, Dialect::Libra);
+        assert_eq!(
+            fmt_ty(&v, Dialect::Libra),
+            format!("&{0}::Coins::Coin<vector<{0}::Coins::Coin>, u64>", hex)
+        );
+        let wallet = display_address(&addr, Dialect::Dfinance);
+        assert!(wallet.starts_with("wallet1"));
+        assert_eq!(
+            fmt_ty(&v, Dialect::Dfinance),
+            format!("&{0}::Coins::Coin<vector<{0}::Coins::Coin>, u64>", wallet)
+        );
+    }
+}
@@ -1,4 +1,7 @@
 pub mod ctx;
 pub mod adapt;
 pub mod tmt;
+pub mod helpers;
+pub mod diagram;
 pub mod utils;
+pub mod writer;
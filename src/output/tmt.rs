@@ -2,21 +2,56 @@ extern crate handlebars;
 extern crate handlebars_misc_helpers;
 
 use std::{collections::HashMap, sync::Arc};
+use std::path::{Path, PathBuf};
 use anyhow::{bail, format_err, Result};
 use serde::Serialize;
 use handlebars::*;
-use crate::cli::{OUTPUT_STDOUT, Output, OutputFmt};
+use crate::cli::{Dialect, OUTPUT_STDOUT, Output, OutputFmt};
+use super::utils::path_to_string;
 
-type FilesMap = HashMap<String, String>;
+pub type FilesMap = HashMap<String, String>;
 
 const MAIN_OUTPUT_FILENAME: &str = "output";
+const INDEX_OUTPUT_FILENAME: &str = "index";
 const REPORT_TEMPLATE_NAME: &str = "document";
+const INDEX_TEMPLATE_NAME: &str = "index";
+const STYLE_PARTIAL_NAME: &str = "style";
+const STYLE_FILENAME: &str = "style.css";
 
 mod defaults {
-    pub static REPORT_TEMPLATE_MD_SRC: &str = include_str!("templates/doc.hbs.md");
+    pub static MD_TEMPLATES: &[(&str, &str)] = &[
+        ("document", include_str!("templates/md/document.hbs.md")),
+        ("function", include_str!("templates/md/function.hbs.md")),
+        ("struct", include_str!("templates/md/struct.hbs.md")),
+        ("address", include_str!("templates/md/address.hbs.md")),
+        ("storage", include_str!("templates/md/storage.hbs.md")),
+        ("source", include_str!("templates/md/source.hbs.md")),
+        ("cfg", include_str!("templates/md/cfg.hbs.md")),
+        ("index", include_str!("templates/md/index.hbs.md")),
+    ];
+    pub static HTML_TEMPLATES: &[(&str, &str)] = &[
+        ("document", include_str!("templates/html/document.hbs.html")),
+        ("function", include_str!("templates/html/function.hbs.html")),
+        ("struct", include_str!("templates/html/struct.hbs.html")),
+        ("address", include_str!("templates/html/address.hbs.html")),
+        ("storage", include_str!("templates/html/storage.hbs.html")),
+        ("source", include_str!("templates/html/source.hbs.html")),
+        ("cfg", include_str!("templates/html/cfg.hbs.html")),
+        ("index", include_str!("templates/html/index.hbs.html")),
+    ];
+    pub static REPORT_STYLE_CSS_SRC: &str = include_str!("templates/html/style.css");
 }
 
-pub fn render<Ctx: Serialize>(cfg: &Output, ctx: Ctx) -> Result<()> {
+/// Paths of user's templates by registered name.
+type TemplateSources = HashMap<String, PathBuf>;
+
+/// Renders the report and writes it with `assets` unless they should be inlined.
+pub fn render<Ctx: Serialize>(
+    cfg: &Output,
+    dialect: Dialect,
+    ctx: Ctx,
+    assets: FilesMap,
+) -> Result<()> {
     if !cfg!(target_arch = "wasm32") {
         prepare_fs(&cfg)?;
 
@@ -57,14 +92,18 @@ pub fn render<Ctx: Serialize>(cfg: &Output, ctx: Ctx) -> Result<()> {
         //     })
         // };
 
-        let output = render_fmt(cfg, &ctx)?;
-
-        // TODO: save used diagrams to cfg.dir
-        // TODO: save `output` to cfg.dir
-        error!("OUTPUT:");
-        for (k, v) in output {
-            error!("OUT FILE: {}: {}", k, v);
+        let mut output = render_fmt(
+            cfg,
+            dialect,
+            REPORT_TEMPLATE_NAME,
+            MAIN_OUTPUT_FILENAME,
+            &ctx,
+        )?;
+        if !cfg.inline {
+            output.extend(assets);
         }
+
+        super::writer::write_files(cfg, output)?;
     } else {
         unimplemented!("not yet");
     }
@@ -72,80 +111,329 @@ pub fn render<Ctx: Serialize>(cfg: &Output, ctx: Ctx) -> Result<()> {
     Ok(())
 }
 
+/// Renders the index page of the batch into the root output directory.
+pub fn render_index<Ctx: Serialize>(cfg: &Output, dialect: Dialect, ctx: Ctx) -> Result<()> {
+    prepare_fs(&cfg)?;
+    let output = render_fmt(
+        cfg,
+        dialect,
+        INDEX_TEMPLATE_NAME,
+        INDEX_OUTPUT_FILENAME,
+        &ctx,
+    )?;
+    super::writer::write_files(cfg, output)
+}
+
+/// Name of the report file relative to the output directory.
+pub fn report_file_name(fmt: &OutputFmt) -> String {
+    format!("{}.{}", MAIN_OUTPUT_FILENAME, output_ext(fmt))
+}
+
 fn render_json<Ctx: Serialize>(_cfg: &Output, ctx: Ctx) -> Result<String> {
     serde_json::to_string_pretty(&ctx).map_err(anyhow::Error::msg)
 }
 
-fn render_fmt<Ctx: Serialize>(cfg: &Output, ctx: Ctx) -> Result<FilesMap> {
+fn render_yaml<Ctx: Serialize>(_cfg: &Output, ctx: Ctx) -> Result<String> {
+    serde_yaml::to_string(&ctx).map_err(anyhow::Error::msg)
+}
+
+fn render_fmt<Ctx: Serialize>(
+    cfg: &Output,
+    dialect: Dialect,
+    template: &str,
+    filename: &str,
+    ctx: Ctx,
+) -> Result<FilesMap> {
     let mut files: HashMap<String, String> = Default::default();
+    let filename = format!("{}.{}", filename, output_ext(&cfg.format));
 
     // if simple serialize requested
     match &cfg.format {
         OutputFmt::Json => {
-            files.insert(
-                format!("{}.json", MAIN_OUTPUT_FILENAME),
-                render_json(cfg, &ctx)?,
-            );
+            files.insert(filename, render_json(cfg, &ctx)?);
+            return Ok(files);
+        }
+        OutputFmt::Yaml => {
+            files.insert(filename, render_yaml(cfg, &ctx)?);
             return Ok(files);
         }
-        OutputFmt::Yaml => unimplemented!("not yet"),
         _ => {}
     }
 
-    let hb = setup_tmt(cfg)?;
+    let (hb, sources) = setup_tmt(cfg, dialect)?;
 
-    {
-        // TODO: add rendered diagrams into the ctx
+    if let OutputFmt::Html = &cfg.format {
+        if !cfg.inline {
+            files.insert(
+                STYLE_FILENAME.to_owned(),
+                defaults::REPORT_STYLE_CSS_SRC.to_owned(),
+            );
+        }
     }
 
-    let fileext = match &cfg.format {
-        OutputFmt::Markdown => "md",
-        OutputFmt::Html => unimplemented!("not yet"),
-        _ => unreachable!(),
-    };
-
-    hb.render(REPORT_TEMPLATE_NAME, &ctx)
+    hb.render(template, &ctx)
         .map(|output| {
-            let filename = format!("{}.{}", MAIN_OUTPUT_FILENAME, fileext);
             files.insert(filename, output);
             files
         })
-        .map_err(anyhow::Error::msg)
+        .map_err(|err| render_error(err, &sources))
 }
 
-fn setup_tmt<'hb>(cfg: &Output) -> Result<Handlebars<'hb>> {
+fn template_ext(fmt: &OutputFmt) -> &'static str {
+    match fmt {
+        OutputFmt::Markdown => "md",
+        OutputFmt::Html => "html",
+        _ => unreachable!(),
+    }
+}
+
+fn output_ext(fmt: &OutputFmt) -> &'static str {
+    match fmt {
+        OutputFmt::Json => "json",
+        OutputFmt::Yaml => "yaml",
+        _ => template_ext(fmt),
+    }
+}
+
+fn setup_tmt<'hb>(cfg: &Output, dialect: Dialect) -> Result<(Handlebars<'hb>, TemplateSources)> {
     let mut hb = Handlebars::new();
 
     hb.set_strict_mode(true);
     hb.source_map_enabled(true);
 
     {
-        handlebars_helper!(hex: |v: i64| format!("0x{:x}", v));
-        hb.register_helper("hex", Box::new(hex));
-        // TODO: impl native partial for address
-
+        super::helpers::register(&mut hb, dialect);
         handlebars_misc_helpers::setup_handlebars(&mut hb);
     }
 
-    match &cfg.format {
+    let builtin = match &cfg.format {
         OutputFmt::Markdown => {
-            // TODO: register all defaults:
-            hb.register_template_string(REPORT_TEMPLATE_NAME, defaults::REPORT_TEMPLATE_MD_SRC)?;
-            // hb.register_partial("function", "FN: {{fn}}\nTHIS: {{this}}")?;
-            // TODO: register all templates in the user's templates directory:..
+            hb.register_escape_fn(no_escape);
+            defaults::MD_TEMPLATES
         }
-        OutputFmt::Html => unimplemented!("not yet"),
-        _ => {}
+        OutputFmt::Html => {
+            let style = if cfg.inline {
+                format!("<style>\n{}</style>", defaults::REPORT_STYLE_CSS_SRC)
+            } else {
+                format!("<link rel=\"stylesheet\" href=\"{}\">", STYLE_FILENAME)
+            };
+            hb.register_partial(STYLE_PARTIAL_NAME, style)?;
+            defaults::HTML_TEMPLATES
+        }
+        _ => &[],
+    };
+
+    for (name, src) in builtin {
+        register_tmt(&mut hb, name, src)?;
     }
 
-    Ok(hb)
+    let sources = match &cfg.templates {
+        Some(dir) => register_user_tmt(&mut hb, dir, template_ext(&cfg.format))?,
+        None => Default::default(),
+    };
+
+    Ok((hb, sources))
+}
+
+fn register_tmt(hb: &mut Handlebars, name: &str, src: &str) -> Result<(), TemplateError> {
+    if name == REPORT_TEMPLATE_NAME || name == INDEX_TEMPLATE_NAME {
+        hb.register_template_string(name, src)
+    } else {
+        hb.register_partial(name, src)
+    }
+}
+
+/// Registers every `<name>.hbs.<ext>` file in the `dir` as template or partial `<name>`,
+/// overriding defaults with the same name.
+fn register_user_tmt(hb: &mut Handlebars, dir: &Path, ext: &str) -> Result<TemplateSources> {
+    let suffix = format!(".hbs.{}", ext);
+    let mut sources = TemplateSources::new();
+
+    let mut files: Vec<_> = std::fs::read_dir(dir)
+        .map_err(|err| {
+            format_err!(
+                "Unable to read templates dir {}: {}",
+                path_to_string(dir),
+                err
+            )
+        })?
+        .filter_map(|e| e.ok())
+        .map(|e| e.path())
+        .filter(|p| p.is_file())
+        .collect();
+    files.sort();
+
+    for path in files {
+        let name = match path
+            .file_name()
+            .and_then(|s| s.to_str())
+            .and_then(|s| s.strip_suffix(&suffix))
+        {
+            Some(name) if !name.is_empty() => name.to_owned(),
+            _ => {
+                trace!("skip non-template file {}", path_to_string(&path));
+                continue;
+            }
+        };
+
+        let src = std::fs::read_to_string(&path)?;
+        register_tmt(hb, &name, &src).map_err(|err| template_error(err, &path))?;
+        debug!(
+            "registered template '{}' from {}",
+            name,
+            path_to_string(&path)
+        );
+        sources.insert(name, path);
+    }
+
+    Ok(sources)
+}
+
+fn template_error(err: TemplateError, path: &Path) -> anyhow::Error {
+    format_err!(
+        "{}:{}:{}: invalid template: {}",
+        path_to_string(path),
+        err.line_no.unwrap_or_default(),
+        err.column_no.unwrap_or_default(),
+        err.reason
+    )
+}
+
+fn render_error(err: RenderError, sources: &TemplateSources) -> anyhow::Error {
+    let source = err
+        .template_name
+        .as_ref()
+        .map(|name| match sources.get(name) {
+            Some(path) => path_to_string(path),
+            None => format!("<built-in {}>", name),
+        });
+    match (source, err.line_no) {
+        (Some(source), Some(line)) => format_err!(
+            "{}:{}:{}: {}",
+            source,
+            line,
+            err.column_no.unwrap_or_default(),
+            err.desc
+        ),
+        (Some(source), None) => format_err!("{}: {}", source, err.desc),
+        _ => anyhow::Error::msg(err),
+    }
 }
 
 fn prepare_fs(cfg: &Output) -> Result<()> {
-    if cfg.dir.as_os_str().to_str() != Some(OUTPUT_STDOUT) {
+    if !cfg.is_stdout() {
         let out_dir = cfg.dir.as_path();
         std::fs::create_dir_all(out_dir).map_err(anyhow::Error::msg)
     } else {
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+    use crate::fixtures;
+    use super::*;
+
+    fn output(format: OutputFmt, templates: &Path) -> Output {
+        Output {
+            dir: templates.join("out"),
+            force: false,
+            format,
+            inline: true,
+            templates: Some(templates.to_owned()),
+            decompile: false,
+            focus: None,
+        }
+    }
+
+    fn templates(name: &str, files: &[(&str, &str)]) -> PathBuf {
+        let dir = fixtures::temp_dir(name);
+        std::fs::create_dir_all(&dir).unwrap();
+        for (file, src) in files {
+            std::fs::write(dir.join(file), src).unwrap();
+        }
+        dir
+    }
+
+    fn index_ctx() -> serde_json::Value {
+        json!({
+            "reports": [{
+                "input": "Coins.mv",
+                "address": null,
+                "is_script": false,
+                "report": "Coins/output.html",
+                "rejected": false,
+                "error": null,
+            }]
+        })
+    }
+
+    #[test]
+    fn user_partial_overrides_builtin() {
+        let dir = templates(
+            "tmt-override",
+            &[
+                ("address.hbs.html", "USER-ADDRESS"),
+                // other formats, backups and unrelated files are skipped:
+                ("index.hbs.md", "{{#if"),
+                ("address.hbs.html.bak", "{{#if"),
+                ("notes.txt", "{{#if"),
+            ],
+        );
+        let cfg = output(OutputFmt::Html, &dir);
+        let files = render_fmt(
+            &cfg,
+            Dialect::Libra,
+            INDEX_TEMPLATE_NAME,
+            "index",
+            index_ctx(),
+        )
+        .unwrap();
+        let index = &files["index.html"];
+        assert!(index.contains("<td>USER-ADDRESS</td>"), "{}", index);
+        assert!(index.contains("Coins/output.html"), "{}", index);
+        std::fs::remove_dir_all(&dir).ok();
+    }
+
+    #[test]
+    fn invalid_template_location() {
+        let dir = templates("tmt-invalid", &[("function.hbs.md", "ok\n{{foo bar=}}\n")]);
+        let cfg = output(OutputFmt::Markdown, &dir);
+        let err = render_fmt(
+            &cfg,
+            Dialect::Libra,
+            INDEX_TEMPLATE_NAME,
+            "index",
+            index_ctx(),
+        )
+        .unwrap_err()
+        .to_string();
+        let path = path_to_string(&dir.join("function.hbs.md"));
+        assert!(err.starts_with(&format!("{}:2:", path)), "{}", err);
+        assert!(err.contains("invalid template"), "{}", err);
+        std::fs::remove_dir_all(&dir).ok();
+    }
+
+    #[test]
+    fn render_error_location() {
+        let dir = templates(
+            "tmt-render",
+            &[("index.hbs.md", "# Reports\n{{missing}}\n")],
+        );
+        let cfg = output(OutputFmt::Markdown, &dir);
+        let err = render_fmt(
+            &cfg,
+            Dialect::Libra,
+            INDEX_TEMPLATE_NAME,
+            "index",
+            index_ctx(),
+        )
+        .unwrap_err()
+        .to_string();
+        let path = path_to_string(&dir.join("index.hbs.md"));
+        assert!(err.starts_with(&format!("{}:2:", path)), "{}", err);
+        assert!(err.contains("missing"), "{}", err);
+        std::fs::remove_dir_all(&dir).ok();
+    }
+}
@@ -0,0 +1,168 @@
+use std::fs;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use anyhow::{bail, format_err, Result};
+use crate::cli::Output;
+use super::tmt::FilesMap;
+use super::utils::path_to_string;
+
+/// Writes rendered files to the output directory or stdout.
+pub fn write_files(cfg: &Output, files: FilesMap) -> Result<()> {
+    if cfg.is_stdout() {
+        write_stdout(files)
+    } else {
+        write_dir(&cfg.dir, cfg.force, files)
+    }
+}
+
+fn write_stdout(files: FilesMap) -> Result<()> {
+    let stdout = std::io::stdout();
+    let mut out = stdout.lock();
+
+    let mut files: Vec<_> = files.into_iter().collect();
+    files.sort_by(|(a, _), (b, _)| a.cmp(b));
+    for (name, content) in files {
+        trace!("writing {} to stdout", name);
+        out.write_all(content.as_bytes())?;
+        if !content.ends_with('\n') {
+            out.write_all(b"\n")?;
+        }
+    }
+    out.flush()?;
+    Ok(())
+}
+
+fn write_dir(dir: &Path, force: bool, files: FilesMap) -> Result<()> {
+    let targets: Vec<(PathBuf, String)> = files
+        .into_iter()
+        .map(|(name, content)| (dir.join(name), content))
+        .collect();
+
+    // check all targets before writing anything to not leave half-written report:
+    if !force {
+        let existing: Vec<_> = targets
+            .iter()
+            .filter(|(path, _)| path.exists())
+            .map(|(path, _)| path_to_string(path))
+            .collect();
+        if !existing.is_empty() {
+            bail!(
+                "Output files already exist: [{}]. Use --force to override them.",
+                existing.join(", ")
+            );
+        }
+    }
+
+    for (path, content) in targets {
+        write_atomic(&path, content.as_bytes())?;
+        info!("written {}", path_to_string(&path));
+    }
+    Ok(())
+}
+
+/// Writes `content` into temporary file near the `path` and then renames it,
+/// so the target is never observed partially written.
+pub fn write_atomic(path: &Path, content: &[u8]) -> Result<()> {
+    let parent = path
+        .parent()
+        .ok_or_else(|| format_err!("Invalid output path {}", path_to_string(path)))?;
+    fs::create_dir_all(parent)?;
+
+    let name = path
+        .file_name()
+        .and_then(|s| s.to_str())
+        .ok_or_else(|| format_err!("Invalid output file name {}", path_to_string(path)))?;
+    let tmp = parent.join(format!(".{}.{}.tmp", name, std::process::id()));
+
+    let result = fs::File::create(&tmp)
+        .and_then(|mut f| f.write_all(content).and_then(|_| f.sync_all()))
+        .and_then(|_| fs::rename(&tmp, path));
+
+    if let Err(err) = result {
+        fs::remove_file(&tmp).ok();
+        bail!("Unable to write {}: {}", path_to_string(path), err);
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::fixtures;
+    use super::*;
+
+    fn files(content: &str) -> FilesMap {
+        vec![
+            ("report.md".to_owned(), content.to_owned()),
+            ("deps/0x1/Coins.move".to_owned(), content.to_owned()),
+        ]
+        .into_iter()
+        .collect()
+    }
+
+    fn read(dir: &Path, name: &str) -> String {
+        fs::read_to_string(dir.join(name)).unwrap()
+    }
+
+    #[test]
+    fn write_nested() {
+        let dir = fixtures::temp_dir("write-nested");
+        write_dir(&dir, false, files("first")).unwrap();
+        assert_eq!(read(&dir, "report.md"), "first");
+        assert_eq!(read(&dir, "deps/0x1/Coins.move"), "first");
+
+        // temporary files are renamed:
+        let names: Vec<_> = fs::read_dir(&dir)
+            .unwrap()
+            .map(|e| e.unwrap().file_name().to_string_lossy().into_owned())
+            .collect();
+        assert!(
+            names.iter().all(|name| !name.ends_with(".tmp")),
+            "{:?}",
+            names
+        );
+        fs::remove_dir_all(&dir).ok();
+    }
+
+    #[test]
+    fn existing_without_force() {
+        let dir = fixtures::temp_dir("write-existing");
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(dir.join("report.md"), "old").unwrap();
+
+        let err = write_dir(&dir, false, files("new"))
+            .unwrap_err()
+            .to_string();
+        assert!(err.contains("report.md"), "{}", err);
+        assert!(err.contains("--force"), "{}", err);
+        // nothing is written if any file exists:
+        assert_eq!(read(&dir, "report.md"), "old");
+        assert!(!dir.join("deps/0x1/Coins.move").exists());
+        fs::remove_dir_all(&dir).ok();
+    }
+
+    #[test]
+    fn existing_with_force() {
+        let dir = fixtures::temp_dir("write-force");
+        write_dir(&dir, false, files("old")).unwrap();
+        write_dir(&dir, true, files("new")).unwrap();
+        assert_eq!(read(&dir, "report.md"), "new");
+        assert_eq!(read(&dir, "deps/0x1/Coins.move"), "new");
+        fs::remove_dir_all(&dir).ok();
+    }
+
+    #[test]
+    fn atomic_write() {
+        let dir = fixtures::temp_dir("write-atomic");
+        let path = dir.join("nested").join("file.txt");
+        write_atomic(&path, b"content").unwrap();
+        assert_eq!(fs::read(&path).unwrap(), b"content");
+        assert_eq!(fs::read_dir(path.parent().unwrap()).unwrap().count(), 1);
+
+        // the directory in place of the target can't be replaced, the temporary file is removed:
+        let target = dir.join("target");
+        fs::create_dir_all(target.join("inner")).unwrap();
+        assert!(write_atomic(&target, b"content").is_err());
+        assert_eq!(fs::read_dir(&dir).unwrap().count(), 2);
+        fs::remove_dir_all(&dir).ok();
+    }
+}
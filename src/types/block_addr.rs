@@ -1,14 +1,16 @@
 use std::fmt::{Display, LowerHex, UpperHex, Binary, Formatter, Result};
 use libra::libra_types::account_address::AccountAddress;
+use serde::Serialize;
 use super::FnAddr;
 
-type BlockId = u16;
+/// Offset of the first instruction of the block.
+pub type BlockId = u16;
 
-#[derive(Debug, Clone, Eq, PartialEq, Hash)]
+#[derive(Debug, Clone, Eq, PartialEq, Hash, Serialize)]
 pub struct BlockAddr(FnAddr, BlockId);
 
 impl BlockAddr {
-    pub fn new<F: Into<FnAddr>, S: ToString>(fn_addr: F, id: BlockId) -> Self {
+    pub fn new<F: Into<FnAddr>>(fn_addr: F, id: BlockId) -> Self {
         Self(fn_addr.into(), id)
     }
 
@@ -0,0 +1,177 @@
+use std::convert::TryInto;
+use libra::libra_types::account_address::AccountAddress;
+use libra::vm::file_format::{Constant, SignatureToken};
+use crate::cli::Dialect;
+
+/// Length of the address in the Libra dialect,
+/// compat layer pads it with leading zeros up to `AccountAddress::LENGTH`.
+const LIBRA_ADDR_LEN: usize = 16;
+
+/// Value of the constant from the constant pool.
+#[derive(Debug, Clone, Eq, PartialEq, Hash)]
+pub enum ConstValue {
+    Bool(bool),
+    U8(u8),
+    U64(u64),
+    U128(u128),
+    Address(AccountAddress),
+    /// `vector<u8>`
+    Bytes(Vec<u8>),
+    Vector(Vec<ConstValue>),
+}
+
+impl ConstValue {
+    /// Decodes LCS-serialized data of the constant, `None` if it's malformed.
+    pub fn decode(constant: &Constant) -> Option<Self> {
+        let mut data = &constant.data[..];
+        let value = decode_value(&constant.type_, &mut data)?;
+        if data.is_empty() {
+            Some(value)
+        } else {
+            None
+        }
+    }
+
+    /// Move literal of the value.
+    pub fn to_literal(&self, dialect: Dialect) -> String {
+        match self {
+            ConstValue::Bool(v) => v.to_string(),
+            ConstValue::U8(v) => format!("{}u8", v),
+            ConstValue::U64(v) => v.to_string(),
+            ConstValue::U128(v) => format!("{}u128", v),
+            ConstValue::Address(addr) => fmt_address(addr, dialect),
+            ConstValue::Bytes(bytes) => format!("x\"{}\"", hex::encode(bytes)),
+            ConstValue::Vector(items) => {
+                let items: Vec<_> = items.iter().map(|v| v.to_literal(dialect)).collect();
+                format!("[{}]", items.join(", "))
+            }
+        }
+    }
+
+    /// Bytes as text if they are printable UTF-8.
+    pub fn as_utf8(&self) -> Option<&str> {
+        match self {
+            ConstValue::Bytes(bytes) if !bytes.is_empty() => std::str::from_utf8(bytes)
+                .ok()
+                .filter(|s| s.chars().all(|c| !c.is_control() || c == '\n')),
+            _ => None,
+        }
+    }
+}
+
+/// Formats the address as it's written in sources of the dialect.
+pub fn fmt_address(addr: &AccountAddress, dialect: Dialect) -> String {
+    let bytes = addr.as_ref();
+    match dialect {
+        Dialect::Libra if bytes.len() > LIBRA_ADDR_LEN => {
+            let (padding, addr) = bytes.split_at(bytes.len() - LIBRA_ADDR_LEN);
+            if padding.iter().all(|b| *b == 0) {
+                format!("0x{}", hex::encode(addr))
+            } else {
+                format!("0x{}", hex::encode(bytes))
+            }
+        }
+        _ => format!("0x{}", hex::encode(bytes)),
+    }
+}
+
+fn decode_value(ty: &SignatureToken, data: &mut &[u8]) -> Option<ConstValue> {
+    Some(match ty {
+        SignatureToken::Bool => match take(data, 1)? {
+            [0] => ConstValue::Bool(false),
+            [1] => ConstValue::Bool(true),
+            _ => return None,
+        },
+        SignatureToken::U8 => ConstValue::U8(take(data, 1)?[0]),
+        SignatureToken::U64 => ConstValue::U64(u64::from_le_bytes(take(data, 8)?.try_into().ok()?)),
+        SignatureToken::U128 => {
+            ConstValue::U128(u128::from_le_bytes(take(data, 16)?.try_into().ok()?))
+        }
+        SignatureToken::Address => {
+            let bytes = take(data, AccountAddress::LENGTH)?;
+            ConstValue::Address(AccountAddress::new(bytes.try_into().ok()?))
+        }
+        SignatureToken::Vector(inner) => {
+            let len = read_uleb128(data)?;
+            if **inner == SignatureToken::U8 {
+                ConstValue::Bytes(take(data, len)?.to_vec())
+            } else {
+                let items = (0..len)
+                    .map(|_| decode_value(inner, data))
+                    .collect::<Option<_>>()?;
+                ConstValue::Vector(items)
+            }
+        }
+        _ => return None,
+    })
+}
+
+fn take<'a>(data: &mut &'a [u8], len: usize) -> Option<&'a [u8]> {
+    if data.len() < len {
+        return None;
+    }
+    let (head, tail) = data.split_at(len);
+    *data = tail;
+    Some(head)
+}
+
+fn read_uleb128(data: &mut &[u8]) -> Option<usize> {
+    let mut value = 0usize;
+    for i in 0..10 {
+        let byte = take(data, 1)?[0];
+        value |= ((byte & 0x7f) as usize) << (7 * i);
+        if byte & 0x80 == 0 {
+            return Some(value);
+        }
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn constant(type_: SignatureToken, data: Vec<u8>) -> Constant {
+        Constant { type_, data }
+    }
+
+    #[test]
+    fn decode_u64() {
+        let c = constant(SignatureToken::U64, 42u64.to_le_bytes().to_vec());
+        assert_eq!(Some(ConstValue::U64(42)), ConstValue::decode(&c));
+    }
+
+    #[test]
+    fn decode_malformed() {
+        let c = constant(SignatureToken::U64, vec![1, 2, 3]);
+        assert_eq!(None, ConstValue::decode(&c));
+        let c = constant(SignatureToken::Bool, vec![1, 0]);
+        assert_eq!(None, ConstValue::decode(&c));
+    }
+
+    #[test]
+    fn decode_bytes() {
+        let c = constant(
+            SignatureToken::Vector(Box::new(SignatureToken::U8)),
+            vec![3, b'X', b'F', b'I'],
+        );
+        let value = ConstValue::decode(&c).unwrap();
+        assert_eq!("x\"584649\"", value.to_literal(Dialect::Dfinance));
+        assert_eq!(Some("XFI"), value.as_utf8());
+    }
+
+    #[test]
+    fn fmt_address_dialect() {
+        let mut bytes = [0; AccountAddress::LENGTH];
+        bytes[AccountAddress::LENGTH - 1] = 1;
+        let addr = AccountAddress::new(bytes);
+        assert_eq!(
+            "0x00000000000000000000000000000001",
+            fmt_address(&addr, Dialect::Libra)
+        );
+        assert_eq!(
+            "0x0000000000000000000000000000000000000001",
+            fmt_address(&addr, Dialect::Dfinance)
+        );
+    }
+}
@@ -1,6 +1,8 @@
 use std::fmt::{Display, LowerHex, UpperHex, Binary, Formatter, Result};
 use libra::libra_types::account_address::AccountAddress;
+use std::str::FromStr;
 use serde::Serialize;
+use super::parse::{self, AddrParseError};
 use super::ModAddr;
 
 #[derive(Debug, Clone, Eq, PartialEq, Hash, Serialize)]
@@ -70,6 +72,16 @@ impl LowerHex for FnAddr {
 
 // TODO: impl UpperHex for FnAddr
 
+/// Parses `0x1::Module::function`, the address can be short hex or bech32.
+impl FromStr for FnAddr {
+    type Err = AddrParseError;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        let parts = parse::split(s, 3, "`ADDRESS::Module::function`", false)?;
+        Ok(Self(parse::module(s, &parts)?, parts[2].to_owned()))
+    }
+}
+
 impl Binary for FnAddr {
     fn fmt(&self, f: &mut Formatter<'_>) -> Result {
         Binary::fmt(&self.0, f).and_then(|_| write!(f, "::{}", self.1))
@@ -92,6 +104,25 @@ mod tests {
         assert_eq!("::Foo::foo", &addr[(addr.len() - 10)..]);
     }
 
+    #[test]
+    fn fn_addr_from_str() {
+        let addr: FnAddr = "0x1::Foo::foo".parse().unwrap();
+        assert_eq!("foo", addr.name());
+        assert_eq!("Foo", addr.addr().name());
+
+        let full = format!("{:#x}", addr_42());
+        assert_eq!(addr_42(), full.parse().unwrap());
+
+        assert!(matches!(
+            "0x1::Foo".parse::<FnAddr>(),
+            Err(AddrParseError::Parts { .. })
+        ));
+        assert!(matches!(
+            "0x1::Foo::foo<u8>".parse::<FnAddr>(),
+            Err(AddrParseError::TypeArgs { .. })
+        ));
+    }
+
     #[test]
     #[ignore]
     fn fn_addr_fmt_bin() {
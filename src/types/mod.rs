@@ -2,13 +2,19 @@ mod mod_addr;
 mod fn_addr;
 mod struct_addr;
 mod block_addr;
+mod const_value;
+mod wallet;
+mod parse;
 
 pub use mod_addr::*;
 pub use fn_addr::*;
 pub use struct_addr::*;
 pub use block_addr::*;
+pub use const_value::*;
+pub use wallet::*;
+pub use parse::AddrParseError;
 
-use libra::vm::file_format::{CompiledModule, SignatureToken, Kind, CompiledScript};
+use libra::vm::file_format::{CompiledModule, SignatureToken, Kind, CompiledScript, StructHandleIndex};
 use libra::vm::access::ModuleAccess;
 use libra::{move_core_types::language_storage::ModuleId, vm::access::ScriptAccess};
 use serde::Serialize;
@@ -53,11 +59,24 @@ pub enum Ty {
 
     /// MOVE user type, resource or copyable
     Struct(StructAddr),
-    // StructInstantiation(StructHandleIndex, Vec<SignatureToken>),
-    // Type parameter.
+
+    /// Generic user type instantiated with type arguments, e.g. `Coin<XFI>`.
+    StructInstantiation(StructAddr, Vec<Ty>),
+
+    /// Type parameter.
     TypeParameter(u16),
 }
 
+/// Parses the type in Move syntax, e.g. `0x1::Coins::Balance<0x1::Coins::ETH>`.
+/// References and type parameters are not supported.
+impl std::str::FromStr for Ty {
+    type Err = AddrParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        parse::ty(s)
+    }
+}
+
 pub fn extract_ty(sign_token: &SignatureToken, compiled_mod: &CompiledModule) -> Ty {
     match sign_token {
         SignatureToken::Bool => Ty::Bool,
@@ -71,21 +90,29 @@ pub fn extract_ty(sign_token: &SignatureToken, compiled_mod: &CompiledModule) ->
         SignatureToken::MutableReference(ty) => {
             Ty::MutableReference(Box::new(extract_ty(ty, compiled_mod)))
         }
-        SignatureToken::Struct(idx) => {
-            let struct_handle = compiled_mod.struct_handle_at(idx.clone());
-            let struct_name = compiled_mod
-                .identifier_at(struct_handle.name)
-                .as_str()
-                .to_string();
-            let module_id = compiled_mod
-                .module_id_for_handle(compiled_mod.module_handle_at(struct_handle.module));
-            Ty::Struct(StructAddr::new(module_id, struct_name))
-        }
+        SignatureToken::Struct(idx) => Ty::Struct(extract_struct_addr(*idx, compiled_mod)),
+        SignatureToken::StructInstantiation(idx, ty_args) => Ty::StructInstantiation(
+            extract_struct_addr(*idx, compiled_mod),
+            ty_args
+                .iter()
+                .map(|ty| extract_ty(ty, compiled_mod))
+                .collect(),
+        ),
         SignatureToken::TypeParameter(idx) => Ty::TypeParameter(idx.to_owned()),
-        _ => todo!(),
     }
 }
 
+fn extract_struct_addr(idx: StructHandleIndex, compiled_mod: &CompiledModule) -> StructAddr {
+    let struct_handle = compiled_mod.struct_handle_at(idx);
+    let struct_name = compiled_mod
+        .identifier_at(struct_handle.name)
+        .as_str()
+        .to_string();
+    let module_id =
+        compiled_mod.module_id_for_handle(compiled_mod.module_handle_at(struct_handle.module));
+    StructAddr::new(module_id, struct_name)
+}
+
 pub fn extract_ty_scrpt(sign_token: &SignatureToken, compiled: &CompiledScript) -> Ty {
     match sign_token {
         SignatureToken::Bool => Ty::Bool,
@@ -99,20 +126,28 @@ pub fn extract_ty_scrpt(sign_token: &SignatureToken, compiled: &CompiledScript)
         SignatureToken::MutableReference(ty) => {
             Ty::MutableReference(Box::new(extract_ty_scrpt(ty, compiled)))
         }
-        SignatureToken::Struct(idx) => {
-            let struct_handle = compiled.struct_handle_at(idx.clone());
-            let struct_name = compiled
-                .identifier_at(struct_handle.name)
-                .as_str()
-                .to_string();
-            let module_handle = compiled.module_handle_at(struct_handle.module);
-            let module_id = ModuleId::new(
-                *compiled.address_identifier_at(module_handle.address),
-                compiled.identifier_at(module_handle.name).to_owned(),
-            );
-            Ty::Struct(StructAddr::new(module_id, struct_name))
-        }
+        SignatureToken::Struct(idx) => Ty::Struct(extract_struct_addr_scrpt(*idx, compiled)),
+        SignatureToken::StructInstantiation(idx, ty_args) => Ty::StructInstantiation(
+            extract_struct_addr_scrpt(*idx, compiled),
+            ty_args
+                .iter()
+                .map(|ty| extract_ty_scrpt(ty, compiled))
+                .collect(),
+        ),
         SignatureToken::TypeParameter(idx) => Ty::TypeParameter(idx.to_owned()),
-        _ => todo!(),
     }
 }
+
+fn extract_struct_addr_scrpt(idx: StructHandleIndex, compiled: &CompiledScript) -> StructAddr {
+    let struct_handle = compiled.struct_handle_at(idx);
+    let struct_name = compiled
+        .identifier_at(struct_handle.name)
+        .as_str()
+        .to_string();
+    let module_handle = compiled.module_handle_at(struct_handle.module);
+    let module_id = ModuleId::new(
+        *compiled.address_identifier_at(module_handle.address),
+        compiled.identifier_at(module_handle.name).to_owned(),
+    );
+    StructAddr::new(module_id, struct_name)
+}
@@ -3,7 +3,9 @@ use std::fmt::{Display, LowerHex, UpperHex, Binary, Formatter, Result};
 use libra::libra_types::account_address::AccountAddress;
 use libra::move_core_types::identifier::IdentStr;
 use libra::move_core_types::language_storage::ModuleId;
+use std::str::FromStr;
 use serde::Serialize;
+use super::parse::{self, AddrParseError};
 
 #[derive(Debug, Clone, Eq, PartialEq, Hash, Serialize)]
 pub struct ModAddr(pub AccountAddress, pub String);
@@ -77,6 +79,16 @@ impl LowerHex for ModAddr {
 
 // TODO: impl UpperHex for ModAddr
 
+/// Parses `0x1::Module`, the address can be short hex or bech32.
+impl FromStr for ModAddr {
+    type Err = AddrParseError;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        let parts = parse::split(s, 2, "`ADDRESS::Module`", false)?;
+        parse::module(s, &parts)
+    }
+}
+
 // TODO: fix binary fmt impl
 impl Binary for ModAddr {
     fn fmt(&self, f: &mut Formatter<'_>) -> Result {
@@ -122,6 +134,33 @@ mod tests {
         assert_eq!("::Foo", &addr[(addr.len() - 5)..]);
     }
 
+    #[test]
+    fn mod_addr_from_str() {
+        let addr: ModAddr = "0x1::Foo".parse().unwrap();
+        assert_eq!("Foo", addr.name());
+        assert_eq!(1, addr.addr().as_ref()[AccountAddress::LENGTH - 1]);
+
+        let full = format!("{:#x}", ModAddr::test_addr_42());
+        assert_eq!(ModAddr::test_addr_42(), full.parse().unwrap());
+
+        assert!(matches!(
+            "0x1".parse::<ModAddr>(),
+            Err(AddrParseError::Parts { .. })
+        ));
+        assert!(matches!(
+            "0xZ::Foo".parse::<ModAddr>(),
+            Err(AddrParseError::Address { .. })
+        ));
+        assert!(matches!(
+            "0x1::9Foo".parse::<ModAddr>(),
+            Err(AddrParseError::Identifier { .. })
+        ));
+        assert!(matches!(
+            "0x1::Foo<u8>".parse::<ModAddr>(),
+            Err(AddrParseError::TypeArgs { .. })
+        ));
+    }
+
     #[test]
     #[ignore]
     fn mod_addr_fmt_bin() {
@@ -0,0 +1,190 @@
+use std::fmt::{Display, Formatter, Result};
+use super::{parse_address, ModAddr, StructAddr, Ty};
+
+/// Error of parsing `ModAddr`, `FnAddr` or `StructAddr` from a string.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub enum AddrParseError {
+    /// Wrong number of `::`-separated parts.
+    Parts {
+        input: String,
+        expected: &'static str,
+    },
+    /// Account address is neither hex nor bech32.
+    Address { input: String, reason: String },
+    /// Module, function or struct name isn't a valid Move identifier.
+    Identifier { input: String, name: String },
+    /// Type arguments are allowed for structs only and must be enclosed in `<>`.
+    TypeArgs { input: String },
+    /// Type argument is neither a primitive, a vector nor a struct.
+    Type { input: String, ty: String },
+}
+
+impl Display for AddrParseError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result {
+        match self {
+            AddrParseError::Parts { input, expected } => {
+                write!(f, "Invalid address '{}': expected {}", input, expected)
+            }
+            AddrParseError::Address { input, reason } => {
+                write!(f, "Invalid address '{}': {}", input, reason)
+            }
+            AddrParseError::Identifier { input, name } => write!(
+                f,
+                "Invalid address '{}': '{}' is not a valid identifier",
+                input, name
+            ),
+            AddrParseError::TypeArgs { input } => {
+                write!(f, "Invalid address '{}': malformed type arguments", input)
+            }
+            AddrParseError::Type { input, ty } => write!(
+                f,
+                "Invalid address '{}': '{}' is not a valid type",
+                input, ty
+            ),
+        }
+    }
+}
+
+impl std::error::Error for AddrParseError {}
+
+/// Splits `ADDRESS::Name[::Name]` into parts, `expected` describes the format for errors.
+/// Type arguments (`<...>`) are validated and dropped if `allow_type_args`, see `type_args`.
+pub(super) fn split<'a>(
+    input: &'a str,
+    parts: usize,
+    expected: &'static str,
+    allow_type_args: bool,
+) -> std::result::Result<Vec<&'a str>, AddrParseError> {
+    let input = input.trim();
+    let path = match input.find('<') {
+        Some(start) if allow_type_args && is_balanced(&input[start..]) => {
+            type_args(&input[start..]).map_err(|err| match err {
+                // invalid types are reported along with the whole input:
+                AddrParseError::Type { ty, .. } => AddrParseError::Type {
+                    input: input.to_owned(),
+                    ty,
+                },
+                err => err,
+            })?;
+            &input[..start]
+        }
+        Some(_) => {
+            return Err(AddrParseError::TypeArgs {
+                input: input.to_owned(),
+            })
+        }
+        None if input.contains('>') => {
+            return Err(AddrParseError::TypeArgs {
+                input: input.to_owned(),
+            })
+        }
+        None => input,
+    };
+
+    let split: Vec<_> = path.split("::").collect();
+    if split.len() != parts {
+        return Err(AddrParseError::Parts {
+            input: input.to_owned(),
+            expected,
+        });
+    }
+    if let Some(name) = split[1..].iter().find(|name| !is_identifier(name)) {
+        return Err(AddrParseError::Identifier {
+            input: input.to_owned(),
+            name: (*name).to_owned(),
+        });
+    }
+    Ok(split)
+}
+
+/// Parses the first two parts as the module address.
+pub(super) fn module(input: &str, parts: &[&str]) -> std::result::Result<ModAddr, AddrParseError> {
+    let address = parse_address(parts[0]).map_err(|err| AddrParseError::Address {
+        input: input.trim().to_owned(),
+        reason: err.to_string(),
+    })?;
+    Ok(ModAddr::new(address, parts[1]))
+}
+
+/// Parses the type in Move syntax: a primitive, `vector<T>` or a struct with type arguments.
+pub(super) fn ty(input: &str) -> std::result::Result<Ty, AddrParseError> {
+    let input = input.trim();
+    let parsed = match input {
+        "bool" => Ty::Bool,
+        "u8" => Ty::U8,
+        "u64" => Ty::U64,
+        "u128" => Ty::U128,
+        "address" => Ty::Address,
+        "signer" => Ty::Signer,
+        _ if input.starts_with("vector<") && is_balanced(&input["vector".len()..]) => {
+            Ty::Vector(Box::new(ty(&input["vector<".len()..input.len() - 1])?))
+        }
+        _ if input.contains("::") => {
+            let parts = split(input, 3, "`ADDRESS::Module::Struct`", true)?;
+            let addr = StructAddr::new(module(input, &parts)?, parts[2]);
+            match input.find('<') {
+                Some(start) => Ty::StructInstantiation(addr, type_args(&input[start..])?),
+                None => Ty::Struct(addr),
+            }
+        }
+        _ => {
+            return Err(AddrParseError::Type {
+                input: input.to_owned(),
+                ty: input.to_owned(),
+            })
+        }
+    };
+    Ok(parsed)
+}
+
+/// Parses balanced `<T1, T2, ...>` into types.
+fn type_args(s: &str) -> std::result::Result<Vec<Ty>, AddrParseError> {
+    let inner = &s[1..s.len() - 1];
+    let mut args = Vec::new();
+    let mut depth = 0;
+    let mut start = 0;
+    for (i, c) in inner.char_indices() {
+        match c {
+            '<' => depth += 1,
+            '>' => depth -= 1,
+            ',' if depth == 0 => {
+                args.push(&inner[start..i]);
+                start = i + 1;
+            }
+            _ => {}
+        }
+    }
+    args.push(&inner[start..]);
+
+    args.into_iter().map(ty).collect()
+}
+
+/// `<...>` with balanced nested brackets and nothing after the closing one.
+fn is_balanced(s: &str) -> bool {
+    let mut depth = 0;
+    for (i, c) in s.char_indices() {
+        match c {
+            '<' => depth += 1,
+            '>' if depth == 0 => return false,
+            '>' => {
+                depth -= 1;
+                if depth == 0 {
+                    return i == s.len() - 1 && i > 1;
+                }
+            }
+            _ => {}
+        }
+    }
+    false
+}
+
+/// Move identifier: `[a-zA-Z_][a-zA-Z0-9_]*`, except the single `_`.
+fn is_identifier(s: &str) -> bool {
+    let mut chars = s.chars();
+    match chars.next() {
+        Some(c) if c.is_ascii_alphabetic() => {}
+        Some('_') if s.len() > 1 => {}
+        _ => return false,
+    }
+    chars.all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
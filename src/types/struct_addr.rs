@@ -1,6 +1,8 @@
 use std::fmt::{Display, LowerHex, UpperHex, Binary, Formatter, Result};
 use libra::libra_types::account_address::AccountAddress;
+use std::str::FromStr;
 use serde::Serialize;
+use super::parse::{self, AddrParseError};
 use super::ModAddr;
 
 pub type ResAddr = StructAddr;
@@ -72,6 +74,18 @@ impl LowerHex for StructAddr {
 
 // TODO: impl UpperHex for StructAddr
 
+/// Parses `0x1::Module::Struct`, the address can be short hex or bech32.
+/// Type arguments like `0x1::Coins::Balance<0x1::Coins::ETH>` are validated, but not kept,
+/// parse `Ty` to keep them.
+impl FromStr for StructAddr {
+    type Err = AddrParseError;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        let parts = parse::split(s, 3, "`ADDRESS::Module::Struct`", true)?;
+        Ok(Self(parse::module(s, &parts)?, parts[2].to_owned()))
+    }
+}
+
 impl Binary for StructAddr {
     fn fmt(&self, f: &mut Formatter<'_>) -> Result {
         Binary::fmt(&self.0, f).and_then(|_| write!(f, "::{}", self.1))
@@ -80,6 +94,7 @@ impl Binary for StructAddr {
 
 #[cfg(test)]
 mod tests {
+    use crate::types::Ty;
     use super::*;
 
     fn addr_42() -> StructAddr {
@@ -94,6 +109,69 @@ mod tests {
         assert_eq!("::Foo::FOO", &addr[(addr.len() - 10)..]);
     }
 
+    #[test]
+    fn struct_addr_from_str() {
+        let addr: StructAddr = "0x1::Foo::FOO".parse().unwrap();
+        assert_eq!("FOO", addr.name());
+
+        let generic: StructAddr = "0x1::Foo::FOO<0x1::Bar::T<u8>, bool>".parse().unwrap();
+        assert_eq!(addr, generic);
+
+        let full = format!("{:#x}", addr_42());
+        assert_eq!(addr_42(), full.parse().unwrap());
+
+        assert!(matches!(
+            "0x1::Foo::FOO<u8".parse::<StructAddr>(),
+            Err(AddrParseError::TypeArgs { .. })
+        ));
+        assert!(matches!(
+            "0x1::Foo::FOO<u8>>".parse::<StructAddr>(),
+            Err(AddrParseError::TypeArgs { .. })
+        ));
+        assert!(matches!(
+            "0x1::Foo::FOO::Bar".parse::<StructAddr>(),
+            Err(AddrParseError::Parts { .. })
+        ));
+        assert!(matches!(
+            "0x1::Foo::FOO<u16>".parse::<StructAddr>(),
+            Err(AddrParseError::Type { ty, .. }) if ty == "u16"
+        ));
+        assert!(matches!(
+            "0x1::Foo::FOO<u8,>".parse::<StructAddr>(),
+            Err(AddrParseError::Type { .. })
+        ));
+        assert!(matches!(
+            "0x1::Foo::FOO<0x1::Bar::1T>".parse::<StructAddr>(),
+            Err(AddrParseError::Identifier { name, .. }) if name == "1T"
+        ));
+        assert!(matches!(
+            "0x1::Foo::FOO<0x1::Bar>".parse::<StructAddr>(),
+            Err(AddrParseError::Parts { .. })
+        ));
+    }
+
+    #[test]
+    fn ty_from_str() {
+        let foo: StructAddr = "0x1::Foo::FOO".parse().unwrap();
+        let bar: StructAddr = "0x1::Bar::T".parse().unwrap();
+        assert_eq!(Ty::U64, " u64 ".parse().unwrap());
+        assert_eq!(Ty::Struct(foo.to_owned()), "0x1::Foo::FOO".parse().unwrap());
+        assert_eq!(
+            Ty::StructInstantiation(
+                foo,
+                vec![
+                    Ty::StructInstantiation(bar, vec![Ty::U8]),
+                    Ty::Vector(Box::new(Ty::Bool)),
+                ]
+            ),
+            "0x1::Foo::FOO<0x1::Bar::T<u8>, vector<bool>>"
+                .parse()
+                .unwrap()
+        );
+        assert!("vector<>".parse::<Ty>().is_err());
+        assert!("&u8".parse::<Ty>().is_err());
+    }
+
     #[test]
     #[ignore]
     fn fn_addr_fmt_bin() {
@@ -0,0 +1,106 @@
+use std::convert::TryFrom;
+use anyhow::{bail, format_err, Result};
+use bech32::{FromBase32, ToBase32};
+use libra::libra_types::account_address::AccountAddress;
+use crate::cli::Dialect;
+use super::fmt_address;
+
+/// Human-readable part of Dfinance wallet addresses, e.g. `wallet1...`.
+pub const WALLET_HRP: &str = "wallet";
+
+/// Formats the address as Dfinance wallet address.
+pub fn to_bech32(addr: &AccountAddress) -> String {
+    bech32::encode(WALLET_HRP, addr.as_ref().to_base32())
+        .expect("wallet HRP is valid and the address is short enough")
+}
+
+/// Parses Dfinance wallet address.
+pub fn from_bech32(s: &str) -> Result<AccountAddress> {
+    let (hrp, data) =
+        bech32::decode(s).map_err(|err| format_err!("Invalid bech32 address '{}': {}", s, err))?;
+    if hrp != WALLET_HRP {
+        bail!(
+            "Invalid bech32 address '{}': expected '{}' prefix, found '{}'",
+            s,
+            WALLET_HRP,
+            hrp
+        );
+    }
+    let bytes = Vec::<u8>::from_base32(&data)
+        .map_err(|err| format_err!("Invalid bech32 address '{}': {}", s, err))?;
+    AccountAddress::try_from(bytes.as_slice()).map_err(|_| {
+        format_err!(
+            "Invalid bech32 address '{}': expected {} bytes, found {}",
+            s,
+            AccountAddress::LENGTH,
+            bytes.len()
+        )
+    })
+}
+
+/// Parses the address written as hex with or without `0x` (leading zeros can be omitted)
+/// or as Dfinance wallet address.
+pub fn parse_address(s: &str) -> Result<AccountAddress> {
+    if s.starts_with(WALLET_HRP) {
+        return from_bech32(s);
+    }
+    let digits = s.strip_prefix("0x").unwrap_or(s);
+    if digits.is_empty() || digits.len() > AccountAddress::LENGTH * 2 {
+        bail!(
+            "Invalid address '{}': expected 1 to {} hex digits",
+            s,
+            AccountAddress::LENGTH * 2
+        );
+    }
+    let padded = format!("{:0>width$}", digits, width = AccountAddress::LENGTH * 2);
+    let bytes =
+        hex::decode(&padded).map_err(|err| format_err!("Invalid address '{}': {}", s, err))?;
+    AccountAddress::try_from(bytes.as_slice()).map_err(|_| format_err!("Invalid address '{}'", s))
+}
+
+/// Formats the address for reports: Dfinance wallet address or hex literal.
+pub fn display_address(addr: &AccountAddress, dialect: Dialect) -> String {
+    match dialect {
+        Dialect::Dfinance => to_bech32(addr),
+        Dialect::Libra => fmt_address(addr, dialect),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn std_addr() -> AccountAddress {
+        let mut bytes = [0; AccountAddress::LENGTH];
+        bytes[AccountAddress::LENGTH - 1] = 1;
+        AccountAddress::new(bytes)
+    }
+
+    #[test]
+    fn bech32_roundtrip() {
+        let addr = std_addr();
+        let wallet = to_bech32(&addr);
+        assert!(wallet.starts_with("wallet1"));
+        assert_eq!(addr, from_bech32(&wallet).unwrap());
+        assert_eq!(addr, parse_address(&wallet).unwrap());
+    }
+
+    #[test]
+    fn bech32_wrong_prefix() {
+        let other = bech32::encode("cosmos", std_addr().as_ref().to_base32()).unwrap();
+        assert!(from_bech32(&other).is_err());
+    }
+
+    #[test]
+    fn parse_hex() {
+        assert_eq!(std_addr(), parse_address("0x1").unwrap());
+        assert_eq!(std_addr(), parse_address("1").unwrap());
+        assert_eq!(
+            std_addr(),
+            parse_address("0x0000000000000000000000000000000000000001").unwrap()
+        );
+        assert!(parse_address("0x").is_err());
+        assert!(parse_address("0xZZ").is_err());
+        assert!(parse_address("0x000000000000000000000000000000000000000001").is_err());
+    }
+}